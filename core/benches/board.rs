@@ -0,0 +1,128 @@
+use core::{Direction, Game, GameConfig};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const SIZES: [u16; 3] = [100, 1000, 2000];
+
+// A snake as wide as the board on a wrapping board, so every tick has to
+// check a long body. Without food it never grows into its own tail. A rewind
+// of 0 leaves out the history the default config keeps.
+fn board(size: u16, food: u16, rewind: usize) -> Game {
+    let mut game = Game::from_config(
+        GameConfig::builder()
+            .size(size, size)
+            .start_pos((size - 1, size / 2))
+            .start_direction(Direction::Right)
+            .start_length(size - 1)
+            .wrap(true)
+            .food_amount(food)
+            .rewind(rewind)
+            .build(),
+    )
+    .unwrap()
+    .with_seed(1);
+    game.start().unwrap();
+
+    game
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+
+    for size in SIZES {
+        let mut game = board(size, 0, 0);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| game.next().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn tick_with_rewind(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick_with_rewind");
+
+    for size in SIZES {
+        let mut game = board(size, 0, GameConfig::default().rewind);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| game.next().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn spawn_food(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn_food");
+
+    for size in SIZES {
+        let mut game = board(size, 1, 0);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                game.set_food(vec![]).unwrap();
+                game.generate_food().unwrap();
+            })
+        });
+    }
+
+    group.finish();
+}
+
+// Each lookup against the grid next to the scan over bodies, food and walls
+// it replaced, on the same board.
+fn occupancy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_occupied");
+
+    for size in SIZES {
+        let game = board(size, 1, 0);
+        let mut x = 0;
+        group.bench_with_input(BenchmarkId::new("grid", size), &size, |b, _| {
+            b.iter(|| {
+                x = (x + 7919) % size;
+                game.is_occupied((x, size / 2))
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("scan", size), &size, |b, _| {
+            b.iter(|| {
+                x = (x + 7919) % size;
+                let pos = (x, size / 2);
+                game.snakes()
+                    .iter()
+                    .any(|snake| snake.body().contains(&pos))
+                    || game.food().iter().any(|food| food.pos == pos)
+                    || game.walls().contains(&pos)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn self_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("self_collision");
+
+    for size in SIZES {
+        let game = board(size, 0, 0);
+        let snake = &game.snakes()[0];
+        group.bench_with_input(BenchmarkId::new("grid", size), &size, |b, _| {
+            b.iter(|| game.self_collision(snake.id).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("scan", size), &size, |b, _| {
+            b.iter(|| {
+                let pos = game.next_pos(snake.id).unwrap();
+                snake.body().iter().skip(1).any(|cell| *cell == pos)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    tick,
+    tick_with_rewind,
+    spawn_food,
+    occupancy,
+    self_collision
+);
+criterion_main!(benches);
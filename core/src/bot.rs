@@ -0,0 +1,765 @@
+//! Bots that steer a snake on their own.
+//!
+//! The [`Autopilot`] takes the shortest path to the nearest food, but only
+//! after playing the path out and checking it could still reach its own tail
+//! from there. If it can't, it follows its tail instead until the way opens.
+//! Once a path is found safe it keeps to it, so a tie between two equally
+//! short paths can't take it down the one it never checked.
+//!
+//! The [`Random`] bot turns at random, as a baseline to compare against.
+//!
+//! The [`Hamiltonian`] solver follows a cycle through every cell of the
+//! board, cutting across it towards food while the snake is short enough to
+//! do so safely. It is slower, but doesn't get stuck and can fill the board.
+
+use std::collections::VecDeque;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{Controller, Direction, Error, FoodKind, Game, GameConfig, PowerUpKind, SnakeId};
+
+const UNREACHED: u32 = u32::MAX;
+
+const SHORTCUT_MARGIN: u32 = 3;
+
+#[derive(Clone, Debug, Default)]
+pub struct Autopilot {
+    queue: VecDeque<usize>,
+    dist: Vec<u32>,
+    parent: Vec<usize>,
+    plans: Vec<Vec<usize>>,
+}
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot::default()
+    }
+
+    // Shortest path to the nearest food worth eating, without the head. Food
+    // that shrinks is only worth it when there is nothing else.
+    fn food_path(
+        &mut self,
+        game: &Game,
+        board: &Board,
+        free_at: &[u32],
+        head: usize,
+        heading: Direction,
+    ) -> Option<Vec<usize>> {
+        let picky = game.food().iter().any(|food| !avoid(food.kind));
+        let (_, target) = self.search(board, free_at, head, heading, |cell| {
+            game.food_at(board.pos(cell))
+                .is_some_and(|food| !picky || !avoid(food.kind))
+        });
+        let target = target?;
+
+        let mut path = vec![target];
+        while let Some(&cell) = path.last() {
+            match self.parent[cell] {
+                parent if parent == head => break,
+                parent => path.push(parent),
+            }
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    // The rest of the path taken last tick, if it still starts next to the
+    // head, leads to food and is clear in time.
+    fn planned(
+        &mut self,
+        game: &Game,
+        board: &Board,
+        id: SnakeId,
+        free_at: &[u32],
+        head: usize,
+    ) -> Option<Vec<usize>> {
+        let path = std::mem::take(self.plans.get_mut(id.0)?);
+        let (first, last) = (*path.first()?, *path.last()?);
+
+        let open = path
+            .iter()
+            .enumerate()
+            .all(|(i, cell)| free_at[*cell] <= i as u32 + 1);
+        let fed = game.food_at(board.pos(last)).is_some();
+
+        (open && fed && board.direction(head, first).is_some()).then_some(path)
+    }
+
+    // With no safe way to food, take the move that keeps the tail reachable
+    // by the longest way round. Failing that, the tail might still come
+    // within reach once the body in the way clears, and failing that too,
+    // take the move with the most room. A cell next to another head is only
+    // worth the risk when the clear moves would trap the snake, and when
+    // nothing is open at all, take the cell that clears soonest rather than
+    // carrying on into a wall.
+    fn stall(
+        &mut self,
+        game: &Game,
+        board: &Board,
+        id: SnakeId,
+        body: &[usize],
+        grow: u32,
+        heading: Direction,
+    ) -> Option<Direction> {
+        let head = *body.last()?;
+        let free_at = board.free_at(game, id, body, grow, 0);
+        let mut best: Option<((u8, bool, u32), Direction)> = None;
+        let mut soonest: Option<(u32, Direction)> = None;
+
+        for direction in moves(heading) {
+            let Some(next) = board.neighbor(head, direction) else {
+                continue;
+            };
+            let pos = board.pos(next);
+            let clear = free_at[next] <= 1;
+            let taken = game.is_occupied(pos) && !game.is_food(pos);
+            if !clear && taken {
+                if free_at[next] != UNREACHED
+                    && soonest.is_none_or(|(ticks, _)| free_at[next] < ticks)
+                {
+                    soonest = Some((free_at[next], direction));
+                }
+                continue;
+            }
+
+            let (body, grow) = board.walk(game, body, grow, &[next]);
+            let mut free_at = board.free_at(game, id, &body, grow, 1);
+            let (reached, _) = self.search(board, &free_at, next, direction, |_| false);
+            let clears = self.dist[body[0]];
+            pin(&mut free_at, &body);
+            self.search(board, &free_at, next, direction, |_| false);
+            let tail = match self.reaches_tail(game, board, id, &free_at, body[0]) {
+                true => self.dist[body[0]],
+                false => UNREACHED,
+            };
+
+            let (tier, room) = if body.len() == 1 {
+                ((reached > 1) as u8 * 3, reached as u32)
+            } else if tail != UNREACHED {
+                (3, tail)
+            } else if clears != UNREACHED {
+                (2, clears)
+            } else {
+                ((reached > body.len()) as u8, reached as u32)
+            };
+            let key = match clear {
+                true => (tier, true, room),
+                false => (tier.saturating_sub(2), false, room),
+            };
+
+            if best.is_none_or(|(best, _)| key > best) {
+                best = Some((key, direction));
+            }
+        }
+
+        best.map(|(_, direction)| direction)
+            .or(soonest.map(|(_, direction)| direction))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn is_safe(
+        &mut self,
+        game: &Game,
+        board: &Board,
+        id: SnakeId,
+        body: &[usize],
+        grow: u32,
+        heading: Direction,
+        elapsed: u32,
+    ) -> bool {
+        let (Some(&head), Some(&tail)) = (body.last(), body.first()) else {
+            return false;
+        };
+        let mut free_at = board.free_at(game, id, body, grow, elapsed);
+        pin(&mut free_at, body);
+        let (reached, _) = self.search(board, &free_at, head, heading, |_| false);
+
+        match body.len() {
+            1 => reached > 1,
+            _ => self.reaches_tail(game, board, id, &free_at, tail),
+        }
+    }
+
+    // Whether the last search got to the tail after it moved on. Food on the
+    // way there holds the tail back by however much it grows the snake, and
+    // so does food a magnet can pull into the way.
+    fn reaches_tail(
+        &self,
+        game: &Game,
+        board: &Board,
+        id: SnakeId,
+        free_at: &[u32],
+        tail: usize,
+    ) -> bool {
+        if self.dist[tail] == UNREACHED {
+            return false;
+        }
+
+        let mut growth = 0;
+        let mut cell = self.parent[tail];
+        while self.dist[cell] > 0 {
+            growth += game
+                .food_at(board.pos(cell))
+                .map_or(0, |food| food.kind.growth());
+            cell = self.parent[cell];
+        }
+
+        if game
+            .snake(id)
+            .is_some_and(|snake| snake.has_power_up(PowerUpKind::Magnet))
+        {
+            let reach = game.config().power_ups.magnet_radius + 1;
+            let (x, y) = board.pos(cell);
+            growth += game
+                .food()
+                .iter()
+                .filter(|food| board.reach(food.pos, (x, y)) <= reach)
+                .map(|food| food.kind.growth())
+                .sum::<u32>();
+        }
+
+        self.dist[tail] >= free_at[tail] + growth
+    }
+
+    // Breadth first search from `start` over cells that are free by the time
+    // the head gets there. The first step can't turn back on `heading`.
+    // Stops early at the first cell matching `is_target`. Returns how many
+    // cells it reached and the target, if any.
+    fn search(
+        &mut self,
+        board: &Board,
+        free_at: &[u32],
+        start: usize,
+        heading: Direction,
+        is_target: impl Fn(usize) -> bool,
+    ) -> (usize, Option<usize>) {
+        self.dist.clear();
+        self.dist.resize(board.len(), UNREACHED);
+        self.parent.clear();
+        self.parent.resize(board.len(), start);
+        self.queue.clear();
+
+        self.dist[start] = 0;
+        self.queue.push_back(start);
+        let mut reached = 1;
+
+        while let Some(cell) = self.queue.pop_front() {
+            let dist = self.dist[cell] + 1;
+            let turns = moves(heading);
+            let directions = match cell == start {
+                true => &turns[..],
+                false => &Direction::ALL[..],
+            };
+
+            for &direction in directions {
+                let Some(next) = board.neighbor(cell, direction) else {
+                    continue;
+                };
+                if self.dist[next] != UNREACHED || free_at[next] > dist {
+                    continue;
+                }
+
+                self.dist[next] = dist;
+                self.parent[next] = cell;
+                reached += 1;
+
+                if is_target(next) {
+                    return (reached, Some(next));
+                }
+                self.queue.push_back(next);
+            }
+        }
+
+        (reached, None)
+    }
+}
+
+impl Controller for Autopilot {
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction> {
+        let snake = game.snake(id).filter(|snake| snake.alive)?;
+        let board = Board::new(game);
+        let body: Vec<usize> = snake.body().iter().map(|pos| board.index(*pos)).collect();
+        let head = *body.last()?;
+        let free_at = board.free_at(game, id, &body, snake.grow, 0);
+
+        let found = self.food_path(game, &board, &free_at, head, snake.direction);
+        let planned = self.planned(game, &board, id, &free_at, head);
+        let path = [found, planned].into_iter().flatten().find(|path| {
+            let (body, grow) = board.walk(game, &body, snake.grow, path);
+            let from = path.len().checked_sub(2).map_or(head, |i| path[i]);
+            let heading = board.direction(from, path[path.len() - 1]);
+            heading.is_some_and(|heading| {
+                self.is_safe(game, &board, id, &body, grow, heading, path.len() as u32)
+            })
+        });
+
+        let direction = match path {
+            Some(mut path) => {
+                let direction = board.direction(head, path.remove(0));
+                if self.plans.len() <= id.0 {
+                    self.plans.resize(id.0 + 1, vec![]);
+                }
+                self.plans[id.0] = path;
+                direction
+            }
+            None => self.stall(game, &board, id, &body, snake.grow, snake.direction),
+        }?;
+
+        (direction != snake.direction).then_some(direction)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Random {
+    rng: ChaCha8Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Controller for Random {
+    fn next_direction(&mut self, _: &Game, _: SnakeId) -> Option<Direction> {
+        Some(Direction::ALL[self.rng.random_range(0..Direction::ALL.len())])
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Hamiltonian {
+    board: Board,
+    order: Vec<u32>,
+    fallback: Autopilot,
+}
+
+impl Hamiltonian {
+    /// Builds the cycle for the board in `config`. There is none if both
+    /// sides are odd, if a side is a single cell, or if there are walls.
+    pub fn new(config: &GameConfig) -> Result<Hamiltonian, Error> {
+        let (width, height) = (config.width, config.height);
+
+        if width < 2 || height < 2 || (width % 2 == 1 && height % 2 == 1) {
+            return Err(Error::InvalidConfig(format!(
+                "a {}x{} board has no Hamiltonian cycle",
+                width, height
+            )));
+        }
+
+        if !config.walls.is_empty() {
+            return Err(Error::InvalidConfig(
+                "no Hamiltonian cycle on a board with walls".into(),
+            ));
+        }
+
+        let board = Board {
+            width,
+            height,
+            wrap: config.wrap,
+        };
+        let mut order = vec![0; board.len()];
+        let cycle = match height % 2 {
+            0 => cycle(width, height),
+            _ => cycle(height, width)
+                .into_iter()
+                .map(|(x, y)| (y, x))
+                .collect(),
+        };
+
+        for (i, pos) in cycle.into_iter().enumerate() {
+            order[board.index(pos)] = i as u32;
+        }
+
+        Ok(Hamiltonian {
+            board,
+            order,
+            fallback: Autopilot::new(),
+        })
+    }
+}
+
+impl Controller for Hamiltonian {
+    // Off the cycle, like at the start or when another snake is in the way,
+    // the move is left to the autopilot.
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction> {
+        let snake = game.snake(id).filter(|snake| snake.alive)?;
+        let board = &self.board;
+        let cells = self.order.len() as u32;
+        let head = board.index(snake.head_pos);
+        let tail = board.index(*snake.body().front()?);
+        let rel = |cell: usize| (self.order[cell] + cells - self.order[head]) % cells;
+
+        // Everything up to the first segment ahead on the cycle is free.
+        let ahead = game
+            .alive_snakes()
+            .flat_map(|snake| snake.body().iter())
+            .map(|pos| rel(board.index(*pos)))
+            .filter(|ahead| *ahead > 0)
+            .min()
+            .unwrap_or(cells);
+        let food = game
+            .food()
+            .iter()
+            .map(|food| rel(board.index(food.pos)))
+            .min();
+        let length = snake.body().len() as u32 + snake.grow;
+        let mut best: Option<(u32, Direction)> = None;
+
+        for direction in moves(snake.direction) {
+            let Some(next) = board.neighbor(head, direction) else {
+                continue;
+            };
+            let step = rel(next);
+            let growth = game
+                .food_at(board.pos(next))
+                .map_or(0, |food| food.kind.growth());
+
+            // A shortcut must not skip food, and has to leave more room ahead
+            // than the snake is long. It leaves gaps in the body that only
+            // close once the tail gets there, and until then food eaten on
+            // the way eats into that room.
+            let fits = match step {
+                1 => ahead > 1 || (next == tail && snake.grow == 0),
+                _ => {
+                    food.is_some_and(|food| step <= food)
+                        && step + length + growth + SHORTCUT_MARGIN < ahead
+                }
+            };
+
+            if fits && best.is_none_or(|(best, _)| step > best) {
+                best = Some((step, direction));
+            }
+        }
+
+        match best {
+            Some((_, direction)) => (direction != snake.direction).then_some(direction),
+            None => self.fallback.next_direction(game, id),
+        }
+    }
+}
+
+// A cycle for an even height: along the top row, then back and forth over
+// the other columns and up the first one.
+fn cycle(width: u16, height: u16) -> Vec<(u16, u16)> {
+    let mut cycle: Vec<(u16, u16)> = (0..width).map(|x| (x, 0)).collect();
+
+    for y in 1..height {
+        match y % 2 {
+            1 => cycle.extend((1..width).rev().map(|x| (x, y))),
+            _ => cycle.extend((1..width).map(|x| (x, y))),
+        }
+    }
+    cycle.extend((1..height).rev().map(|y| (0, y)));
+
+    cycle
+}
+
+// Only the tail is let go of. Counting on the rest of the body clearing in
+// time finds ways to the tail that cut through the body, and once there the
+// snake can't be sure to keep following it.
+fn pin(free_at: &mut [u32], body: &[usize]) {
+    for cell in body.iter().skip(1) {
+        free_at[*cell] = UNREACHED;
+    }
+}
+
+fn avoid(kind: FoodKind) -> bool {
+    matches!(kind, FoodKind::Poison | FoodKind::Shrink)
+}
+
+// Directions a snake heading this way is allowed to take, straight first.
+fn moves(heading: Direction) -> [Direction; 3] {
+    [heading, heading.turn_left(), heading.turn_right()]
+}
+
+#[derive(Clone, Debug)]
+struct Board {
+    width: u16,
+    height: u16,
+    wrap: bool,
+}
+
+impl Board {
+    fn new(game: &Game) -> Board {
+        Board {
+            width: game.width(),
+            height: game.height(),
+            wrap: game.config().wrap,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    fn index(&self, pos: (u16, u16)) -> usize {
+        pos.1 as usize * self.width as usize + pos.0 as usize
+    }
+
+    fn pos(&self, index: usize) -> (u16, u16) {
+        (
+            (index % self.width as usize) as u16,
+            (index / self.width as usize) as u16,
+        )
+    }
+
+    fn neighbor(&self, index: usize, direction: Direction) -> Option<usize> {
+        let (x, y) = self.pos(index);
+        let (dx, dy) = direction.value();
+        let (mut x, mut y) = (x as i32 + dx, y as i32 + dy);
+
+        if self.wrap {
+            x = x.rem_euclid(self.width as i32);
+            y = y.rem_euclid(self.height as i32);
+        } else if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+
+        Some(self.index((x as u16, y as u16)))
+    }
+
+    // The larger of the steps across and down between `a` and `b`, taking the
+    // short way round on a wrapping board like the magnet does.
+    fn reach(&self, a: (u16, u16), b: (u16, u16)) -> u16 {
+        let (x, y) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+
+        match self.wrap {
+            true => x.min(self.width - x).max(y.min(self.height - y)),
+            false => x.max(y),
+        }
+    }
+
+    fn direction(&self, from: usize, to: usize) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|direction| self.neighbor(from, *direction) == Some(to))
+    }
+
+    // How many ticks until each cell is free to move into. Body segments
+    // clear from the tail, later if the snake still has to grow, and cells
+    // next to another head are kept clear of a head-on crash.
+    fn free_at(
+        &self,
+        game: &Game,
+        id: SnakeId,
+        body: &[usize],
+        grow: u32,
+        elapsed: u32,
+    ) -> Vec<u32> {
+        let mut free_at = vec![0; self.len()];
+
+        for wall in game.walls() {
+            free_at[self.index(*wall)] = UNREACHED;
+        }
+
+        for snake in game.alive_snakes().filter(|snake| snake.id != id) {
+            for (i, pos) in snake.body().iter().enumerate() {
+                let ticks = (i as u32 + 1 + snake.grow).saturating_sub(elapsed);
+                free_at[self.index(*pos)] = ticks;
+            }
+
+            if elapsed == 0 {
+                for direction in Direction::ALL {
+                    if let Some(cell) = self.neighbor(self.index(snake.head_pos), direction) {
+                        free_at[cell] = free_at[cell].max(2);
+                    }
+                }
+            }
+        }
+
+        for (i, cell) in body.iter().enumerate() {
+            free_at[*cell] = i as u32 + 1 + grow;
+        }
+
+        free_at
+    }
+
+    // The body and growth left after following `path`, eating on the way.
+    fn walk(&self, game: &Game, body: &[usize], grow: u32, path: &[usize]) -> (Vec<usize>, u32) {
+        let mut body = VecDeque::from(body.to_vec());
+        let mut grow = grow;
+
+        for cell in path {
+            if let Some(food) = game.food_at(self.pos(*cell)) {
+                grow += food.kind.growth();
+            }
+
+            body.push_back(*cell);
+            match grow {
+                0 => {
+                    body.pop_front();
+                }
+                _ => grow -= 1,
+            }
+        }
+
+        (body.into(), grow)
+    }
+}
+
+mod test {
+    use crate::*;
+
+    fn drive(game: &mut Game, ticks: usize) {
+        let mut autopilot = Autopilot::new();
+
+        for _ in 0..ticks {
+            if game.state() != GameState::Running {
+                break;
+            }
+            if let Some(direction) = autopilot.next_direction(game, SnakeId(0)) {
+                game.input(SnakeId(0), direction).unwrap();
+            }
+            game.next().unwrap();
+        }
+    }
+
+    #[test]
+    fn autopilot_should_reach_food() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(10, 10)
+                .start_pos((0, 0))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(4, 6)]).unwrap();
+        game.start().unwrap();
+
+        drive(&mut game, 10);
+
+        assert!(game.food().is_empty());
+        assert_eq!(game.snakes[0].body.len(), 2);
+        assert_eq!(game.snakes[0].score.points, 10);
+    }
+
+    #[test]
+    fn autopilot_should_not_enter_dead_end() {
+        let level = Level::parse(
+            "\
+            .........<\n\
+            ..........\n\
+            #####.....\n\
+            #F........\n\
+            #####.....",
+        )
+        .unwrap();
+        let mut game = level.into_game().unwrap();
+        let body = (2..10).rev().map(|x| (x, 0)).collect();
+        game.set_snake(SnakeId(0), Snake::from_body(body, Direction::Left).unwrap())
+            .unwrap();
+        game.start().unwrap();
+
+        drive(&mut game, 30);
+
+        assert!(game.snakes[0].alive);
+        assert_eq!(game.food().len(), 1);
+    }
+
+    #[test]
+    fn autopilot_should_not_crash_on_open_board() {
+        let config = sim::SimConfig {
+            game: GameConfig::builder().size(12, 12).build(),
+            games: 20,
+            threads: 2,
+            seed: 1,
+            max_ticks: 400,
+        };
+
+        let report = sim::run(&config, |_| Box::new(Autopilot::new())).unwrap();
+        assert!(report.scores().median >= 100);
+    }
+
+    #[test]
+    fn autopilot_should_not_run_into_walls_or_itself() {
+        // The autopilot used to trap itself on this seed at tick 3292.
+        let config = sim::SimConfig {
+            game: Level::parse(include_str!("../../levels/box.txt"))
+                .unwrap()
+                .config,
+            games: 1,
+            threads: 1,
+            seed: 84,
+            max_ticks: 3_400,
+        };
+
+        let report = sim::run(&config, |_| Box::new(Autopilot::new())).unwrap();
+        assert!(
+            report.games.iter().all(|game| game.causes.is_empty()),
+            "{:?}",
+            report.death_causes()
+        );
+    }
+
+    #[test]
+    fn hamiltonian_cycle_should_visit_every_cell() {
+        for (width, height) in [(2, 2), (4, 3), (3, 4), (5, 2), (6, 6), (7, 10)] {
+            let config = GameConfig::builder().size(width, height).build();
+            let solver = bot::Hamiltonian::new(&config).unwrap();
+            let mut cells = vec![0; solver.order.len()];
+
+            for (cell, i) in solver.order.iter().enumerate() {
+                cells[*i as usize] = cell;
+            }
+            for (i, cell) in cells.iter().enumerate() {
+                let next = cells[(i + 1) % cells.len()];
+                assert!(
+                    Direction::ALL
+                        .into_iter()
+                        .any(|direction| solver.board.neighbor(*cell, direction) == Some(next)),
+                    "{}x{} cycle breaks at {}",
+                    width,
+                    height,
+                    i
+                );
+            }
+        }
+
+        for (width, height) in [(3, 3), (1, 4), (5, 1)] {
+            let config = GameConfig::builder().size(width, height).build();
+            assert!(bot::Hamiltonian::new(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn hamiltonian_should_clear_the_board() {
+        // Poison and shrinking food would keep the snake from ever filling it.
+        let food_weights = FoodWeights {
+            poison: 0,
+            shrink: 0,
+            ..FoodWeights::mixed()
+        };
+
+        for (seed, (width, height, wrap)) in [(8, 6, false), (7, 6, false), (6, 7, true)]
+            .into_iter()
+            .enumerate()
+        {
+            let config = GameConfig::builder()
+                .size(width, height)
+                .start_pos((2, 2))
+                .wrap(wrap)
+                .food_weights(food_weights.clone())
+                .rewind(0)
+                .build();
+            let mut solver = bot::Hamiltonian::new(&config).unwrap();
+            let mut game = Game::from_config(config).unwrap().with_seed(seed as u64);
+            game.start().unwrap();
+
+            while game.state() == GameState::Running && game.tick() < 10_000 {
+                if let Some(direction) = solver.next_direction(&game, SnakeId(0)) {
+                    game.input(SnakeId(0), direction).unwrap();
+                }
+                game.next().unwrap();
+            }
+
+            assert_eq!(game.state(), GameState::Won, "{}x{}", width, height);
+            assert_eq!(game.snakes[0].body.len(), (width * height) as usize);
+            assert!(game.food().is_empty());
+        }
+    }
+}
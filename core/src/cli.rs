@@ -0,0 +1,140 @@
+//! Command line options shared by the binaries.
+//!
+//! Options take a value, as in `--food 3`, unless they are in [`FLAGS`]. The
+//! first argument that is neither an option nor its value is the level.
+
+use std::str::FromStr;
+
+use crate::{Error, Level};
+
+/// Options that take no value.
+pub const FLAGS: &[&str] = &["--wrap", "--relative"];
+
+#[derive(Clone, Debug, Default)]
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    /// `args` without the program name.
+    pub fn new(args: impl IntoIterator<Item = String>) -> Args {
+        Args {
+            args: args.into_iter().collect(),
+        }
+    }
+
+    pub fn from_env() -> Args {
+        Args::new(std::env::args().skip(1))
+    }
+
+    /// The value after `name`, or `None` when `name` isn't given. A value
+    /// that is missing or doesn't parse is an error rather than the default.
+    pub fn value<T: FromStr>(&self, name: &str) -> Result<Option<T>, Error> {
+        let Some(i) = self.args.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        let value = self
+            .args
+            .get(i + 1)
+            .ok_or_else(|| Error::InvalidConfig(format!("{} needs a value", name)))?;
+
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::InvalidConfig(format!("bad value '{}' for {}", value, name)))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg == name)
+    }
+
+    pub fn level_path(&self) -> Option<&str> {
+        let mut args = self.args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                return Some(arg);
+            }
+
+            if !FLAGS.contains(&arg.as_str()) {
+                args.next();
+            }
+        }
+
+        None
+    }
+
+    /// Loads the level given, or parses `default` when there is none, and
+    /// applies `--food`, `--food-kinds`, `--length`, `--rewind`, `--speed` and
+    /// `--wrap` to it.
+    pub fn level(&self, default: &str) -> Result<Level, Error> {
+        let mut level = match self.level_path() {
+            Some(path) => Level::load(path)?,
+            None => Level::parse(default)?,
+        };
+
+        if let Some(food) = self.value("--food")? {
+            level.config.food_amount = food;
+        }
+        if let Some(food_weights) = self.value("--food-kinds")? {
+            level.config.food_weights = food_weights;
+        }
+        if let Some(length) = self.value("--length")? {
+            level.config.start_length = length;
+        }
+        if let Some(rewind) = self.value("--rewind")? {
+            level.config.rewind = rewind;
+        }
+        if let Some(speed) = self.value("--speed")? {
+            level.config.speed = speed;
+        }
+        if self.flag("--wrap") {
+            level.config.wrap = true;
+        }
+
+        Ok(level)
+    }
+}
+
+mod test {
+    use crate::cli::*;
+
+    fn args(line: &str) -> Args {
+        Args::new(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn should_find_the_level_between_options() {
+        assert_eq!(args("--wrap level.txt").level_path(), Some("level.txt"));
+        assert_eq!(
+            args("--relative --food 3 level.txt --wrap").level_path(),
+            Some("level.txt")
+        );
+        assert_eq!(args("--food 3 --seed 7").level_path(), None);
+        assert_eq!(args("--food 3").value::<u16>("--food").unwrap(), Some(3));
+        assert_eq!(args("--seed 3").value::<u16>("--food").unwrap(), None);
+        assert!(args("--food lots").value::<u16>("--food").is_err());
+        assert!(args("--food").value::<u16>("--food").is_err());
+        assert!(args("--relative").flag("--relative"));
+    }
+
+    #[test]
+    fn should_apply_options_to_the_level() {
+        let level = args("--food 4 --food-kinds mixed --length 3 --wrap")
+            .level("length = 2\n>....\n.....")
+            .unwrap();
+
+        assert_eq!(level.config.food_amount, 4);
+        assert_eq!(level.config.food_weights, crate::FoodWeights::mixed());
+        assert_eq!(level.config.start_length, 3);
+        assert!(level.config.wrap);
+
+        let level = args("").level("wrap = true\n>....").unwrap();
+        assert!(level.config.wrap);
+
+        assert!(matches!(
+            args("--speed fast").level(">...."),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
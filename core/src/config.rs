@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+
+use crate::{Direction, Error, FoodWeights, PowerUpConfig, ScoreConfig, SpeedCurve};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnakeStart {
+    pub pos: (u16, u16),
+    pub direction: Direction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    pub width: u16,
+    pub height: u16,
+    pub snakes: Vec<SnakeStart>,
+    pub start_length: u16,
+    pub food_amount: u16,
+    pub wrap: bool,
+    pub walls: Vec<(u16, u16)>,
+    pub food: Vec<(u16, u16)>,
+    pub food_weights: FoodWeights,
+    pub power_ups: PowerUpConfig,
+    pub score: ScoreConfig,
+    pub speed: SpeedCurve,
+    pub input_buffer: usize,
+    pub rewind: usize,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            width: 20,
+            height: 20,
+            snakes: vec![SnakeStart {
+                pos: (5, 5),
+                direction: Direction::Right,
+            }],
+            start_length: 1,
+            food_amount: 1,
+            wrap: false,
+            walls: vec![],
+            food: vec![],
+            food_weights: FoodWeights::default(),
+            power_ups: PowerUpConfig::default(),
+            score: ScoreConfig::default(),
+            speed: SpeedCurve::default(),
+            input_buffer: 3,
+            rewind: 100,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidConfig(format!(
+                "board size {}x{} is empty",
+                self.width, self.height
+            )));
+        }
+
+        if self.start_length == 0 {
+            return Err(Error::InvalidConfig(
+                "start length must be at least 1".into(),
+            ));
+        }
+
+        if self.input_buffer == 0 {
+            return Err(Error::InvalidConfig(
+                "input buffer must hold at least one turn".into(),
+            ));
+        }
+
+        self.speed.validate()?;
+        self.food_weights.validate()?;
+
+        if self.snakes.is_empty() {
+            return Err(Error::InvalidConfig(
+                "there must be at least one snake".into(),
+            ));
+        }
+
+        let mut body = HashSet::new();
+        for start in self.snakes.iter() {
+            if !self.contains(start.pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "start position {:?} is off the board",
+                    start.pos
+                )));
+            }
+
+            for pos in self.start_body(start)? {
+                if !body.insert(pos) {
+                    return Err(Error::InvalidConfig(format!("snakes overlap at {:?}", pos)));
+                }
+            }
+        }
+
+        let mut walls = HashSet::with_capacity(self.walls.len());
+        for wall in self.walls.iter() {
+            if !walls.insert(*wall) {
+                return Err(Error::InvalidConfig(format!(
+                    "wall {:?} is placed twice",
+                    wall
+                )));
+            }
+
+            if !self.contains(*wall) {
+                return Err(Error::InvalidConfig(format!(
+                    "wall {:?} is off the board",
+                    wall
+                )));
+            }
+
+            if body.contains(wall) {
+                return Err(Error::InvalidConfig(format!(
+                    "wall {:?} overlaps the snake",
+                    wall
+                )));
+            }
+        }
+
+        let mut food = HashSet::with_capacity(self.food.len());
+        for pos in self.food.iter() {
+            if !self.contains(*pos) || body.contains(pos) || walls.contains(pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "food {:?} is not on a free cell",
+                    pos
+                )));
+            }
+
+            if !food.insert(*pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "food {:?} is placed twice",
+                    pos
+                )));
+            }
+        }
+
+        let cells = (self.width as u32 * self.height as u32).saturating_sub(walls.len() as u32);
+        if self.food_amount as u32 + body.len() as u32 > cells {
+            return Err(Error::InvalidConfig(format!(
+                "{} food and {} snake cells do not fit in {} free cells",
+                self.food_amount,
+                body.len(),
+                cells
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, pos: (u16, u16)) -> bool {
+        pos.0 < self.width && pos.1 < self.height
+    }
+
+    pub fn start_body(&self, start: &SnakeStart) -> Result<Vec<(u16, u16)>, Error> {
+        let (dx, dy) = start.direction.value();
+        let mut body = Vec::with_capacity(self.start_length as usize);
+
+        for i in (0..self.start_length as i32).rev() {
+            let x = start.pos.0 as i32 - dx * i;
+            let y = start.pos.1 as i32 - dy * i;
+
+            if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                return Err(Error::InvalidConfig(format!(
+                    "snake of length {} starting at {:?} does not fit on the board",
+                    self.start_length, start.pos
+                )));
+            }
+
+            body.push((x as u16, y as u16));
+        }
+
+        Ok(body)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GameConfigBuilder {
+    config: GameConfig,
+}
+
+impl GameConfigBuilder {
+    pub fn width(mut self, width: u16) -> Self {
+        self.config.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: u16) -> Self {
+        self.config.height = height;
+        self
+    }
+
+    pub fn size(self, width: u16, height: u16) -> Self {
+        self.width(width).height(height)
+    }
+
+    pub fn start_pos(mut self, pos: (u16, u16)) -> Self {
+        self.config.snakes[0].pos = pos;
+        self
+    }
+
+    pub fn start_length(mut self, length: u16) -> Self {
+        self.config.start_length = length;
+        self
+    }
+
+    pub fn start_direction(mut self, direction: Direction) -> Self {
+        self.config.snakes[0].direction = direction;
+        self
+    }
+
+    pub fn snake(mut self, pos: (u16, u16), direction: Direction) -> Self {
+        self.config.snakes.push(SnakeStart { pos, direction });
+        self
+    }
+
+    pub fn food_amount(mut self, food_amount: u16) -> Self {
+        self.config.food_amount = food_amount;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.config.wrap = wrap;
+        self
+    }
+
+    pub fn walls(mut self, walls: Vec<(u16, u16)>) -> Self {
+        self.config.walls = walls;
+        self
+    }
+
+    pub fn wall(mut self, pos: (u16, u16)) -> Self {
+        self.config.walls.push(pos);
+        self
+    }
+
+    pub fn food(mut self, pos: (u16, u16)) -> Self {
+        self.config.food.push(pos);
+        self
+    }
+
+    pub fn food_weights(mut self, weights: FoodWeights) -> Self {
+        self.config.food_weights = weights;
+        self
+    }
+
+    pub fn power_ups(mut self, power_ups: PowerUpConfig) -> Self {
+        self.config.power_ups = power_ups;
+        self
+    }
+
+    pub fn score(mut self, score: ScoreConfig) -> Self {
+        self.config.score = score;
+        self
+    }
+
+    pub fn speed(mut self, speed: SpeedCurve) -> Self {
+        self.config.speed = speed;
+        self
+    }
+
+    pub fn input_buffer(mut self, depth: usize) -> Self {
+        self.config.input_buffer = depth;
+        self
+    }
+
+    pub fn rewind(mut self, ticks: usize) -> Self {
+        self.config.rewind = ticks;
+        self
+    }
+
+    pub fn build(self) -> GameConfig {
+        self.config
+    }
+}
@@ -0,0 +1,239 @@
+//! Sources of turns for a snake, so any snake can be driven by a player, a
+//! script, a bot or someone on the other end of a connection.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use crate::{Direction, Game, SnakeId, Turn};
+
+pub trait Controller {
+    /// Asked once per tick while the game runs, before [`Game::next`]. The
+    /// returned turn is handed to [`Game::input`]; `None` keeps going.
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction>;
+}
+
+impl<C: Controller + ?Sized> Controller for Box<C> {
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction> {
+        (**self).next_direction(game, id)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Press {
+    Direction(Direction),
+    Turn(Turn),
+}
+
+/// Key presses, handed out one per tick. Clones share the same presses, so
+/// one can be kept by the key handling code and one given to the game loop.
+#[derive(Clone, Debug, Default)]
+pub struct Keyboard {
+    presses: Arc<Mutex<VecDeque<Press>>>,
+}
+
+impl Keyboard {
+    pub fn new() -> Keyboard {
+        Keyboard::default()
+    }
+
+    pub fn press(&self, direction: Direction) {
+        self.presses
+            .lock()
+            .unwrap()
+            .push_back(Press::Direction(direction));
+    }
+
+    /// Queues a turn from wherever the snake heads when it's handed out.
+    pub fn press_turn(&self, turn: Turn) {
+        self.presses.lock().unwrap().push_back(Press::Turn(turn));
+    }
+
+    pub fn clear(&self) {
+        self.presses.lock().unwrap().clear();
+    }
+}
+
+impl Controller for Keyboard {
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction> {
+        next_turn(&mut self.presses.lock().unwrap(), game, id)
+    }
+}
+
+/// Turns sent from another thread, like one reading them off the network.
+#[derive(Debug)]
+pub struct Remote {
+    receiver: Receiver<Direction>,
+    turns: VecDeque<Press>,
+}
+
+impl Remote {
+    pub fn new(receiver: Receiver<Direction>) -> Remote {
+        Remote {
+            receiver,
+            turns: VecDeque::new(),
+        }
+    }
+
+    pub fn channel() -> (Sender<Direction>, Remote) {
+        let (sender, receiver) = mpsc::channel();
+
+        (sender, Remote::new(receiver))
+    }
+}
+
+impl Controller for Remote {
+    fn next_direction(&mut self, game: &Game, id: SnakeId) -> Option<Direction> {
+        self.turns
+            .extend(self.receiver.try_iter().map(Press::Direction));
+
+        next_turn(&mut self.turns, game, id)
+    }
+}
+
+// The first queued turn the snake can take, skipping the ones it can't and
+// dropping any past what the game would have buffered.
+fn next_turn(turns: &mut VecDeque<Press>, game: &Game, id: SnakeId) -> Option<Direction> {
+    let direction = game.snake(id)?.direction;
+    turns.truncate(game.config().input_buffer);
+
+    while let Some(press) = turns.pop_front() {
+        let turn = match press {
+            Press::Direction(turn) => turn,
+            Press::Turn(turn) => direction.turn(turn),
+        };
+
+        if direction.is_valid_direction(turn) {
+            return Some(turn);
+        }
+    }
+
+    None
+}
+
+/// Turns to take at given ticks, for tests and demos.
+#[derive(Clone, Debug, Default)]
+pub struct Scripted {
+    turns: VecDeque<(u64, Direction)>,
+}
+
+impl Scripted {
+    pub fn new(turns: impl IntoIterator<Item = (u64, Direction)>) -> Scripted {
+        let mut turns: Vec<_> = turns.into_iter().collect();
+        turns.sort_by_key(|(tick, _)| *tick);
+
+        Scripted {
+            turns: turns.into(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.turns.is_empty()
+    }
+}
+
+impl Controller for Scripted {
+    fn next_direction(&mut self, game: &Game, _: SnakeId) -> Option<Direction> {
+        while self.turns.front()?.0 < game.tick() {
+            self.turns.pop_front();
+        }
+
+        match self.turns.front() {
+            Some((tick, direction)) if *tick == game.tick() => {
+                let direction = *direction;
+                self.turns.pop_front();
+                Some(direction)
+            }
+            _ => None,
+        }
+    }
+}
+
+mod test {
+    use crate::*;
+
+    fn drive(game: &mut Game, controller: &mut impl Controller, ticks: usize) {
+        for _ in 0..ticks {
+            if let Some(direction) = controller.next_direction(game, SnakeId(0)) {
+                game.input(SnakeId(0), direction).unwrap();
+            }
+            game.next().unwrap();
+        }
+    }
+
+    fn running() -> Game {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(10, 10)
+                .start_pos((2, 2))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        game
+    }
+
+    #[test]
+    fn scripted_should_turn_at_ticks() {
+        let mut game = running();
+        let mut script = Scripted::new([(2, Direction::Left), (1, Direction::Down)]);
+
+        drive(&mut game, &mut script, 3);
+
+        assert!(script.is_done());
+        assert_eq!(game.snakes[0].head_pos, (2, 3));
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+    }
+
+    #[test]
+    fn keyboard_should_hand_out_one_press_per_tick() {
+        let mut game = running();
+        let mut keyboard = Keyboard::new();
+        let handle = keyboard.clone();
+        handle.press(Direction::Left);
+        handle.press(Direction::Down);
+        handle.press(Direction::Left);
+
+        drive(&mut game, &mut keyboard, 1);
+        assert_eq!(game.snakes[0].direction, Direction::Down);
+
+        drive(&mut game, &mut keyboard, 1);
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        assert_eq!(game.snakes[0].head_pos, (1, 3));
+    }
+
+    #[test]
+    fn keyboard_should_turn_from_heading() {
+        let mut game = running();
+        let mut keyboard = Keyboard::new();
+        keyboard.press_turn(Turn::Left);
+        keyboard.press_turn(Turn::Straight);
+        keyboard.press_turn(Turn::Left);
+
+        drive(&mut game, &mut keyboard, 1);
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+
+        drive(&mut game, &mut keyboard, 1);
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        assert_eq!(game.snakes[0].head_pos, (1, 1));
+    }
+
+    #[test]
+    fn remote_should_take_turns_from_another_thread() {
+        let mut game = running();
+        let (sender, mut remote) = Remote::channel();
+
+        std::thread::spawn(move || sender.send(Direction::Down).unwrap())
+            .join()
+            .unwrap();
+        drive(&mut game, &mut remote, 1);
+
+        assert_eq!(game.snakes[0].head_pos, (2, 3));
+    }
+}
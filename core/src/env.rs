@@ -0,0 +1,440 @@
+//! A reinforcement learning environment in the style of gym, playing by the
+//! exact rules of [`Game::next`].
+//!
+//! The agent drives snake 0. Any other snakes are driven by their own
+//! [`Controller`], or keep going straight.
+
+use crate::{
+    Controller, Direction, Error, FoodKind, Game, GameConfig, GameEvent, GameState, SnakeId, Turn,
+};
+
+const AGENT: SnakeId = SnakeId(0);
+
+const RELATIVE: [Turn; 3] = [Turn::Left, Turn::Straight, Turn::Right];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Absolute(Direction),
+    Relative(Turn),
+}
+
+/// Which actions an agent picks from, so they can be numbered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActionSpace {
+    /// Up, down, left, right.
+    #[default]
+    Absolute,
+    /// Left, straight, right of the current heading.
+    Relative,
+}
+
+impl ActionSpace {
+    pub fn len(&self) -> usize {
+        match self {
+            ActionSpace::Absolute => Direction::ALL.len(),
+            ActionSpace::Relative => RELATIVE.len(),
+        }
+    }
+
+    pub fn action(&self, index: usize) -> Option<Action> {
+        match self {
+            ActionSpace::Absolute => Direction::ALL.get(index).copied().map(Action::Absolute),
+            ActionSpace::Relative => RELATIVE.get(index).copied().map(Action::Relative),
+        }
+    }
+}
+
+/// What the agent is rewarded for each step. Everything adds up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reward {
+    /// For every food eaten, whatever its kind.
+    pub food: f32,
+    /// For every point scored.
+    pub points: f32,
+    pub death: f32,
+    pub win: f32,
+    /// For every step taken, usually a small cost.
+    pub step: f32,
+    /// For moving closer to the nearest food, and taken away for moving
+    /// further from it.
+    pub closer: f32,
+}
+
+impl Default for Reward {
+    fn default() -> Self {
+        Reward {
+            food: 1.0,
+            points: 0.0,
+            death: -1.0,
+            win: 1.0,
+            step: 0.0,
+            closer: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvConfig {
+    pub game: GameConfig,
+    pub actions: ActionSpace,
+    pub reward: Reward,
+    /// Steps before an episode is cut short, 0 for no limit.
+    pub max_steps: u64,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            game: GameConfig::default(),
+            actions: ActionSpace::default(),
+            reward: Reward::default(),
+            max_steps: 10_000,
+        }
+    }
+}
+
+/// The board as planes of `height` rows of `width` cells, one after the
+/// other, followed by the agent's heading:
+///
+/// 0. the agent's body, rising from the tail to 1 at the head
+/// 1. the other snakes' bodies, the same way
+/// 2. walls
+/// 3. food
+/// 4. food that shrinks, like poison
+///
+/// then one value each for up, down, left and right, 1 for the heading.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub width: u16,
+    pub height: u16,
+    pub data: Vec<f32>,
+}
+
+impl Observation {
+    pub const PLANES: usize = 5;
+
+    pub fn new(game: &Game) -> Observation {
+        let (width, height) = (game.width(), game.height());
+        let cells = width as usize * height as usize;
+        let mut data = vec![0.0; Observation::PLANES * cells + Direction::ALL.len()];
+        let index = |plane: usize, pos: (u16, u16)| {
+            plane * cells + pos.1 as usize * width as usize + pos.0 as usize
+        };
+
+        for snake in game.alive_snakes() {
+            let plane = if snake.id == AGENT { 0 } else { 1 };
+            let len = snake.body().len() as f32;
+
+            for (i, pos) in snake.body().iter().enumerate() {
+                data[index(plane, *pos)] = (i + 1) as f32 / len;
+            }
+        }
+
+        for wall in game.walls() {
+            data[index(2, *wall)] = 1.0;
+        }
+
+        for food in game.food() {
+            data[index(3, food.pos)] = 1.0;
+            if matches!(food.kind, FoodKind::Poison | FoodKind::Shrink) {
+                data[index(4, food.pos)] = 1.0;
+            }
+        }
+
+        if let Some(snake) = game.snake(AGENT) {
+            let heading = Direction::iter()
+                .position(|direction| direction == snake.direction)
+                .unwrap_or(0);
+            data[Observation::PLANES * cells + heading] = 1.0;
+        }
+
+        Observation {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// The planes as `[planes, height, width]`, without the heading.
+    pub fn shape(&self) -> [usize; 3] {
+        [
+            Observation::PLANES,
+            self.height as usize,
+            self.width as usize,
+        ]
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        &self.data
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Info {
+    pub tick: u64,
+    pub score: u32,
+    pub length: usize,
+    pub events: Vec<GameEvent>,
+    /// Whether the episode ran out of steps rather than ending.
+    pub truncated: bool,
+}
+
+pub struct Env {
+    config: EnvConfig,
+    game: Game,
+    opponents: Vec<(SnakeId, Box<dyn Controller + Send>)>,
+    steps: u64,
+    done: bool,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Result<Env, Error> {
+        // An agent never rewinds, so there is no history to keep each step.
+        let mut game_config = config.game.clone();
+        game_config.rewind = 0;
+
+        let mut game = Game::from_config(game_config)?;
+        game.start()?;
+
+        Ok(Env {
+            config,
+            game,
+            opponents: vec![],
+            steps: 0,
+            done: false,
+        })
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Drives another snake with `controller` from now on.
+    pub fn set_opponent(
+        &mut self,
+        id: SnakeId,
+        controller: Box<dyn Controller + Send>,
+    ) -> Result<(), Error> {
+        if id == AGENT || self.game.snake(id).is_none() {
+            return Err(Error::UnknownSnake(id));
+        }
+
+        self.opponents.retain(|(other, _)| *other != id);
+        self.opponents.push((id, controller));
+
+        Ok(())
+    }
+
+    pub fn observe(&self) -> Observation {
+        Observation::new(&self.game)
+    }
+
+    /// Starts a new episode on a fresh game.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game.set_seed(seed);
+        self.game.reset();
+        let _ = self.game.start();
+        self.steps = 0;
+        self.done = false;
+
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Action) -> Result<(Observation, f32, bool, Info), Error> {
+        if self.done {
+            return Err(Error::EpisodeOver);
+        }
+
+        let agent = self.game.snake(AGENT).ok_or(Error::UnknownSnake(AGENT))?;
+        let score = agent.score.points;
+        let distance = self.food_distance();
+
+        match action {
+            Action::Absolute(direction) => self.game.input(AGENT, direction)?,
+            Action::Relative(turn) => self.game.input_turn(AGENT, turn)?,
+        }
+        for (id, controller) in self.opponents.iter_mut() {
+            if let Some(direction) = controller.next_direction(&self.game, *id) {
+                self.game.input(*id, direction)?;
+            }
+        }
+
+        let events = self.game.next()?;
+        self.steps += 1;
+
+        let reward = self.config.reward;
+        let agent = self.game.snake(AGENT).ok_or(Error::UnknownSnake(AGENT))?;
+        let mut total = reward.step + (agent.score.points - score) as f32 * reward.points;
+
+        for event in events.iter() {
+            total += match event {
+                GameEvent::AteFood { id, .. } if *id == AGENT => reward.food,
+                GameEvent::Died { id, .. } if *id == AGENT => reward.death,
+                GameEvent::Won => reward.win,
+                _ => 0.0,
+            };
+        }
+
+        if let (Some(before), Some(after)) = (distance, self.food_distance()) {
+            total += reward.closer * (before as f32 - after as f32).signum();
+        }
+
+        let ended = !agent.alive || self.game.state() != GameState::Running;
+        let truncated = !ended && self.config.max_steps > 0 && self.steps >= self.config.max_steps;
+        self.done = ended || truncated;
+
+        let info = Info {
+            tick: self.game.tick(),
+            score: agent.score.points,
+            length: agent.body().len(),
+            events,
+            truncated,
+        };
+
+        Ok((self.observe(), total, self.done, info))
+    }
+
+    /// Steps with the action numbered `index` in the configured action space.
+    pub fn step_index(&mut self, index: usize) -> Result<(Observation, f32, bool, Info), Error> {
+        let action = self
+            .config
+            .actions
+            .action(index)
+            .ok_or(Error::InvalidAction(index))?;
+
+        self.step(action)
+    }
+
+    // Steps from the agent's head to the nearest food, across the edges if
+    // they wrap.
+    fn food_distance(&self) -> Option<u32> {
+        let head = self.game.snake(AGENT).filter(|snake| snake.alive)?.head_pos;
+        let wrap = self.game.config().wrap;
+        let axis = |a: u16, b: u16, size: u16| {
+            let d = a.abs_diff(b) as u32;
+            if wrap {
+                d.min(size as u32 - d)
+            } else {
+                d
+            }
+        };
+
+        self.game
+            .food()
+            .iter()
+            .map(|food| {
+                axis(head.0, food.pos.0, self.game.width())
+                    + axis(head.1, food.pos.1, self.game.height())
+            })
+            .min()
+    }
+}
+
+mod test {
+    use crate::*;
+    use env::*;
+
+    fn env(actions: ActionSpace) -> Env {
+        let mut env = Env::new(EnvConfig {
+            game: GameConfig::builder()
+                .size(8, 6)
+                .start_pos((2, 2))
+                .food_amount(0)
+                .build(),
+            actions,
+            reward: Reward {
+                step: -0.01,
+                closer: 0.1,
+                ..Reward::default()
+            },
+            max_steps: 20,
+        })
+        .unwrap();
+        env.reset(1);
+
+        env
+    }
+
+    #[test]
+    fn observation_should_lay_out_planes() {
+        let env = env(ActionSpace::Absolute);
+        let observation = env.observe();
+
+        assert_eq!(observation.shape(), [5, 6, 8]);
+        assert_eq!(observation.as_slice().len(), 5 * 6 * 8 + 4);
+        assert_eq!(observation.data[2 * 8 + 2], 1.0);
+        assert_eq!(observation.data[5 * 48 + 3], 1.0);
+        assert_eq!(observation.data.iter().sum::<f32>(), 2.0);
+    }
+
+    #[test]
+    fn relative_actions_should_turn_from_heading() {
+        let mut env = env(ActionSpace::Relative);
+
+        let (observation, reward, done, _) = env.step_index(0).unwrap();
+        assert_eq!(env.game().snakes[0].head_pos, (2, 1));
+        assert_eq!(observation.data[5 * 48], 1.0);
+        assert_eq!(reward, -0.01);
+        assert!(!done);
+
+        env.step(Action::Relative(Turn::Right)).unwrap();
+        assert_eq!(env.game().snakes[0].head_pos, (3, 1));
+        assert!(matches!(env.step_index(3), Err(Error::InvalidAction(3))));
+        assert_eq!(env.game().tick(), 2);
+    }
+
+    #[test]
+    fn step_should_shape_rewards() {
+        let mut env = env(ActionSpace::Absolute);
+        env.game.set_food(vec![Food::new(4, 2)]).unwrap();
+
+        let (_, reward, _, _) = env.step(Action::Absolute(Direction::Right)).unwrap();
+        assert_eq!(reward, -0.01 + 0.1);
+
+        let (_, reward, _, info) = env.step(Action::Absolute(Direction::Right)).unwrap();
+        assert_eq!(reward, -0.01 + 1.0);
+        assert_eq!(info.length, 2);
+
+        let (_, reward, done, _) = env.step(Action::Absolute(Direction::Up)).unwrap();
+        assert_eq!(reward, -0.01);
+        assert!(!done);
+        env.step(Action::Absolute(Direction::Up)).unwrap();
+        let (_, reward, done, info) = env.step(Action::Absolute(Direction::Up)).unwrap();
+        assert_eq!(reward, -0.01 - 1.0);
+        assert!(done && !info.truncated);
+        assert!(matches!(
+            env.step(Action::Absolute(Direction::Up)),
+            Err(Error::EpisodeOver)
+        ));
+    }
+
+    #[test]
+    fn episode_should_be_cut_short() {
+        let mut env = env(ActionSpace::Relative);
+        let mut turns = [1, 2, 2, 1, 2, 2].into_iter().cycle();
+        let mut steps = 0;
+
+        loop {
+            let (_, _, done, info) = env.step_index(turns.next().unwrap()).unwrap();
+            steps += 1;
+            if done {
+                assert!(info.truncated);
+                break;
+            }
+        }
+
+        assert_eq!(steps, 20);
+        assert_eq!(env.game().rewind_len(), 0);
+        let observation = env.reset(1);
+        assert_eq!(observation, env.observe());
+        assert_eq!(env.game().tick(), 0);
+        assert_eq!(env.game().seed(), 1);
+    }
+}
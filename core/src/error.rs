@@ -0,0 +1,88 @@
+use std::fmt;
+
+use crate::{GameState, SnakeId};
+
+#[derive(Debug)]
+pub enum Error {
+    OutOfBounds {
+        pos: (i32, i32),
+    },
+    Overflow {
+        pos: (i32, i32),
+    },
+    DeadSnake,
+    EpisodeOver,
+    InvalidAction(usize),
+    UnknownSnake(SnakeId),
+    BoardFull,
+    InvalidConfig(String),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Io(std::io::Error),
+    Save(String),
+    UnsupportedVersion(u32),
+    ReplayMismatch {
+        tick: u64,
+        expected: u64,
+        found: u64,
+    },
+    InvalidTransition {
+        from: GameState,
+        to: GameState,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OutOfBounds { pos } => write!(f, "position {:?} is out of bounds", pos),
+            Error::Overflow { pos } => write!(f, "position {:?} overflows the board", pos),
+            Error::DeadSnake => write!(f, "the snake is dead"),
+            Error::EpisodeOver => write!(f, "the episode is over, reset it first"),
+            Error::InvalidAction(index) => write!(f, "there is no action {}", index),
+            Error::UnknownSnake(id) => write!(f, "there is no {}", id),
+            Error::BoardFull => write!(f, "there is no free cell left on the board"),
+            Error::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Save(reason) => write!(f, "invalid save: {}", reason),
+            Error::ReplayMismatch {
+                tick,
+                expected,
+                found,
+            } => write!(
+                f,
+                "replay differs at tick {}: expected state {:016x}, found {:016x}",
+                tick, expected, found
+            ),
+            Error::UnsupportedVersion(version) => {
+                write!(f, "save version {} is not supported", version)
+            }
+            Error::InvalidTransition { from, to } => {
+                write!(f, "cannot go from {} to {}", from, to)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
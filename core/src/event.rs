@@ -0,0 +1,44 @@
+use crate::{DeathCause, FoodKind, PowerUpKind, SnakeId};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent {
+    Moved {
+        id: SnakeId,
+        head: (u16, u16),
+        vacated: Option<(u16, u16)>,
+    },
+    AteFood {
+        id: SnakeId,
+        pos: (u16, u16),
+        kind: FoodKind,
+    },
+    Grew {
+        id: SnakeId,
+    },
+    Shrank {
+        id: SnakeId,
+        vacated: (u16, u16),
+    },
+    FoodMoved {
+        from: (u16, u16),
+        to: (u16, u16),
+    },
+    Absorbed {
+        id: SnakeId,
+        cause: DeathCause,
+    },
+    PowerUpEnded {
+        id: SnakeId,
+        kind: PowerUpKind,
+    },
+    FoodSpawned {
+        pos: (u16, u16),
+        kind: FoodKind,
+    },
+    Died {
+        id: SnakeId,
+        cause: DeathCause,
+    },
+    Won,
+}
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::{Error, PowerUpKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoodKind {
+    Normal,
+    Golden,
+    Poison,
+    SpeedUp,
+    SlowDown,
+    Shrink,
+    Ghost,
+    Shield,
+    Magnet,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 9] = [
+        FoodKind::Normal,
+        FoodKind::Golden,
+        FoodKind::Poison,
+        FoodKind::SpeedUp,
+        FoodKind::SlowDown,
+        FoodKind::Shrink,
+        FoodKind::Ghost,
+        FoodKind::Shield,
+        FoodKind::Magnet,
+    ];
+
+    pub fn points(&self, base: u32) -> u32 {
+        match self {
+            FoodKind::Golden => base.saturating_mul(5),
+            FoodKind::Poison => 0,
+            _ => base,
+        }
+    }
+
+    pub fn growth(&self) -> u32 {
+        match self {
+            FoodKind::Golden => 3,
+            FoodKind::Poison | FoodKind::Shrink => 0,
+            FoodKind::Ghost | FoodKind::Shield | FoodKind::Magnet => 0,
+            _ => 1,
+        }
+    }
+
+    pub fn shrink(&self, length: usize) -> usize {
+        match self {
+            FoodKind::Poison => 2,
+            FoodKind::Shrink => length / 2,
+            _ => 0,
+        }
+    }
+
+    pub fn speed(&self) -> i32 {
+        match self {
+            FoodKind::SpeedUp => 1,
+            FoodKind::SlowDown => -1,
+            _ => 0,
+        }
+    }
+
+    pub fn power_up(&self) -> Option<PowerUpKind> {
+        match self {
+            FoodKind::Ghost => Some(PowerUpKind::Ghost),
+            FoodKind::Shield => Some(PowerUpKind::Shield),
+            FoodKind::Magnet => Some(PowerUpKind::Magnet),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Food {
+    pub pos: (u16, u16),
+    pub kind: FoodKind,
+}
+
+impl Food {
+    pub fn new(x: u16, y: u16) -> Food {
+        Food::with_kind((x, y), FoodKind::Normal)
+    }
+
+    pub fn with_kind(pos: (u16, u16), kind: FoodKind) -> Food {
+        Food { pos, kind }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoodWeights {
+    pub normal: u32,
+    pub golden: u32,
+    pub poison: u32,
+    pub speed_up: u32,
+    pub slow_down: u32,
+    pub shrink: u32,
+    pub ghost: u32,
+    pub shield: u32,
+    pub magnet: u32,
+}
+
+impl Default for FoodWeights {
+    fn default() -> Self {
+        FoodWeights::only(FoodKind::Normal)
+    }
+}
+
+impl FoodWeights {
+    pub fn only(kind: FoodKind) -> FoodWeights {
+        let mut weights = FoodWeights {
+            normal: 0,
+            golden: 0,
+            poison: 0,
+            speed_up: 0,
+            slow_down: 0,
+            shrink: 0,
+            ghost: 0,
+            shield: 0,
+            magnet: 0,
+        };
+        *weights.weight_mut(kind) = 1;
+
+        weights
+    }
+
+    /// Every kind of food, mostly normal with the odd special one.
+    pub fn mixed() -> FoodWeights {
+        FoodWeights {
+            normal: 64,
+            golden: 8,
+            poison: 8,
+            speed_up: 5,
+            slow_down: 5,
+            shrink: 4,
+            ghost: 2,
+            shield: 2,
+            magnet: 2,
+        }
+    }
+
+    pub fn weight(&self, kind: FoodKind) -> u32 {
+        match kind {
+            FoodKind::Normal => self.normal,
+            FoodKind::Golden => self.golden,
+            FoodKind::Poison => self.poison,
+            FoodKind::SpeedUp => self.speed_up,
+            FoodKind::SlowDown => self.slow_down,
+            FoodKind::Shrink => self.shrink,
+            FoodKind::Ghost => self.ghost,
+            FoodKind::Shield => self.shield,
+            FoodKind::Magnet => self.magnet,
+        }
+    }
+
+    fn weight_mut(&mut self, kind: FoodKind) -> &mut u32 {
+        match kind {
+            FoodKind::Normal => &mut self.normal,
+            FoodKind::Golden => &mut self.golden,
+            FoodKind::Poison => &mut self.poison,
+            FoodKind::SpeedUp => &mut self.speed_up,
+            FoodKind::SlowDown => &mut self.slow_down,
+            FoodKind::Shrink => &mut self.shrink,
+            FoodKind::Ghost => &mut self.ghost,
+            FoodKind::Shield => &mut self.shield,
+            FoodKind::Magnet => &mut self.magnet,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        FoodKind::ALL
+            .iter()
+            .fold(0, |total, kind| total.saturating_add(self.weight(*kind)))
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.total() == 0 {
+            return Err(Error::InvalidConfig(
+                "at least one food kind needs a spawn weight".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> FoodKind {
+        let mut roll = rng.random_range(0..self.total().max(1));
+
+        for kind in FoodKind::ALL {
+            let weight = self.weight(kind);
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+
+        FoodKind::Normal
+    }
+}
+
+impl FromStr for FoodWeights {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(FoodWeights::default()),
+            "mixed" => Ok(FoodWeights::mixed()),
+            s => Err(Error::InvalidConfig(format!("unknown food preset '{}'", s))),
+        }
+    }
+}
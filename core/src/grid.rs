@@ -0,0 +1,145 @@
+//! Occupancy of every cell on the board, kept in sync with the snakes, food
+//! and walls of a [`Game`](crate::Game) so lookups don't have to scan them.
+//!
+//! Free cells are also kept in a list with each cell's slot in it, so a free
+//! cell can be taken, given back or picked at random in constant time.
+
+const NOT_FREE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Cell {
+    segments: u16,
+    owner: u16,
+    wall: bool,
+    food: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Grid {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    free: Vec<u32>,
+    slots: Vec<u32>,
+}
+
+impl Grid {
+    pub(crate) fn new(width: u16, height: u16) -> Grid {
+        let size = width as usize * height as usize;
+
+        Grid {
+            width,
+            height,
+            cells: vec![Cell::default(); size],
+            free: (0..size as u32).collect(),
+            slots: (0..size as u32).collect(),
+        }
+    }
+
+    fn index(&self, pos: (u16, u16)) -> usize {
+        debug_assert!(
+            self.contains(pos),
+            "{:?} is off the {}x{} grid",
+            pos,
+            self.width,
+            self.height
+        );
+
+        pos.1 as usize * self.width as usize + pos.0 as usize
+    }
+
+    fn pos(&self, index: u32) -> (u16, u16) {
+        (
+            (index % self.width as u32) as u16,
+            (index / self.width as u32) as u16,
+        )
+    }
+
+    pub(crate) fn contains(&self, pos: (u16, u16)) -> bool {
+        pos.0 < self.width && pos.1 < self.height
+    }
+
+    pub(crate) fn segments(&self, pos: (u16, u16)) -> u16 {
+        self.cells[self.index(pos)].segments
+    }
+
+    pub(crate) fn owner(&self, pos: (u16, u16)) -> usize {
+        self.cells[self.index(pos)].owner as usize
+    }
+
+    pub(crate) fn is_wall(&self, pos: (u16, u16)) -> bool {
+        self.contains(pos) && self.cells[self.index(pos)].wall
+    }
+
+    pub(crate) fn is_food(&self, pos: (u16, u16)) -> bool {
+        self.contains(pos) && self.cells[self.index(pos)].food
+    }
+
+    pub(crate) fn is_free(&self, pos: (u16, u16)) -> bool {
+        self.contains(pos) && self.slots[self.index(pos)] != NOT_FREE
+    }
+
+    pub(crate) fn free_len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub(crate) fn free_at(&self, slot: usize) -> (u16, u16) {
+        self.pos(self.free[slot])
+    }
+
+    pub(crate) fn free_cells(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.free.iter().map(|index| self.pos(*index))
+    }
+
+    pub(crate) fn add_segment(&mut self, pos: (u16, u16), owner: usize) {
+        debug_assert!(
+            owner <= u16::MAX as usize,
+            "snake {} has no owner slot",
+            owner
+        );
+        let index = self.index(pos);
+        self.cells[index].segments += 1;
+        self.cells[index].owner = owner as u16;
+        self.update(index);
+    }
+
+    pub(crate) fn remove_segment(&mut self, pos: (u16, u16)) {
+        let index = self.index(pos);
+        self.cells[index].segments -= 1;
+        self.update(index);
+    }
+
+    pub(crate) fn set_wall(&mut self, pos: (u16, u16)) {
+        let index = self.index(pos);
+        self.cells[index].wall = true;
+        self.update(index);
+    }
+
+    pub(crate) fn set_food(&mut self, pos: (u16, u16), food: bool) {
+        let index = self.index(pos);
+        self.cells[index].food = food;
+        self.update(index);
+    }
+
+    fn update(&mut self, index: usize) {
+        let cell = self.cells[index];
+        let free = cell.segments == 0 && !cell.wall && !cell.food;
+
+        match (free, self.slots[index] != NOT_FREE) {
+            (true, false) => {
+                self.slots[index] = self.free.len() as u32;
+                self.free.push(index as u32);
+            }
+            (false, true) => {
+                let slot = self.slots[index] as usize;
+                let last = *self.free.last().expect("a free cell is in the list");
+                self.free.swap_remove(slot);
+                if last as usize != index {
+                    self.slots[last as usize] = slot as u32;
+                }
+                self.slots[index] = NOT_FREE;
+            }
+            _ => (),
+        }
+    }
+}
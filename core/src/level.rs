@@ -0,0 +1,442 @@
+//! Plain text levels, one character per cell:
+//!
+//! - `#` wall
+//! - `.` empty cell
+//! - `F` food placed when the game starts
+//! - `S` and an arrow, as in `S>`, snake start and heading in one cell
+//! - `^`, `>`, `v`, `<` the same start, written as the arrow alone
+//!
+//! Every row must have the same width and the level needs at least one start.
+//! Each start is its own snake, numbered in reading order.
+//!
+//! Settings the cells can't show go above them, one `name = value` per line:
+//! `length` of the snakes at the start, `wrap` as `true` or `false`, the
+//! amount of `food` kept on the board if it isn't the number of `F` cells, and
+//! `food_kinds = mixed` to spawn special food next to the normal kind. Blank
+//! lines before the board are skipped, but not between its rows.
+
+use std::{fmt, fs, path::Path};
+
+use crate::{Direction, Error, FoodWeights, Game, GameConfig, SnakeStart};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    pub config: GameConfig,
+}
+
+impl Level {
+    pub fn parse(text: &str) -> Result<Level, Error> {
+        let mut config = GameConfig::default();
+        let mut food_amount = None;
+        let mut snakes = vec![];
+        let mut width = None;
+        let mut height = 0;
+        let mut lines = text.trim_end().lines().enumerate().peekable();
+
+        while let Some((line, text)) = lines.peek().copied() {
+            if text.trim().is_empty() {
+                lines.next();
+                continue;
+            }
+            let Some((name, value)) = text.split_once('=') else {
+                break;
+            };
+
+            let column = name.len() + 1 + value.len() - value.trim_start().len();
+            match name.trim() {
+                "length" => config.start_length = Level::value(line, column, value)?,
+                "wrap" => config.wrap = Level::value(line, column, value)?,
+                "food" => food_amount = Some(Level::value(line, column, value)?),
+                "food_kinds" => config.food_weights = Level::value(line, column, value)?,
+                name => {
+                    return Err(Level::error(
+                        line,
+                        0,
+                        &format!("unknown setting '{}'", name),
+                    ));
+                }
+            }
+
+            lines.next();
+        }
+
+        let top = lines.peek().map_or(0, |(line, _)| *line);
+        for (line, text) in lines {
+            let y = line - top;
+            if y >= u16::MAX as usize {
+                return Err(Level::error(line, 0, "level is too tall"));
+            }
+
+            let text = text.trim_end();
+            if text.is_empty() {
+                return Err(Level::error(line, 0, "empty row"));
+            }
+
+            let mut chars = text.chars().enumerate();
+            let mut cells = 0;
+            let mut overflow = None;
+
+            while let Some((column, c)) = chars.next() {
+                if cells >= u16::MAX as usize {
+                    return Err(Level::error(line, column, "level is too wide"));
+                }
+                if width == Some(cells) {
+                    overflow.get_or_insert(column);
+                }
+
+                let pos = (cells as u16, y as u16);
+
+                match c {
+                    '#' => config.walls.push(pos),
+                    '.' => (),
+                    'F' => config.food.push(pos),
+                    'S' => match chars.next().and_then(|(_, c)| Level::heading(c)) {
+                        Some(direction) => snakes.push(SnakeStart { pos, direction }),
+                        None => {
+                            return Err(Level::error(
+                                line,
+                                column,
+                                "a start needs a heading, use S^, S>, Sv or S<",
+                            ));
+                        }
+                    },
+                    c => match Level::heading(c) {
+                        Some(direction) => snakes.push(SnakeStart { pos, direction }),
+                        None => {
+                            return Err(Level::error(
+                                line,
+                                column,
+                                &format!("unknown cell '{}'", c),
+                            ));
+                        }
+                    },
+                }
+
+                cells += 1;
+            }
+
+            match width {
+                None => width = Some(cells),
+                Some(width) if width != cells => {
+                    return Err(Level::error(
+                        line,
+                        overflow.unwrap_or(text.chars().count()),
+                        &format!("expected {} cells, found {}", width, cells),
+                    ));
+                }
+                _ => (),
+            }
+
+            height = y as u16 + 1;
+        }
+
+        let width = match width {
+            Some(width) if width > 0 => width,
+            _ => return Err(Error::InvalidConfig("level is empty".into())),
+        };
+        if snakes.is_empty() {
+            return Err(Error::InvalidConfig("level has no snake start".into()));
+        }
+
+        config.width = width as u16;
+        config.height = height;
+        config.snakes = snakes;
+        if let Some(food_amount) = food_amount {
+            config.food_amount = food_amount;
+        } else if !config.food.is_empty() {
+            config.food_amount = config.food.len() as u16;
+        }
+
+        Ok(Level { config })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Level, Error> {
+        Level::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The board as it is now: a start at the head of every living snake,
+    /// heading the way it moves, and the food and walls in place. The snakes
+    /// start at the level's length again, or shorter where that won't fit.
+    /// A game that can't be written as a loadable level, such as one with no
+    /// living snake, is an error.
+    pub fn from_game(game: &Game) -> Result<Level, Error> {
+        let mut config = game.config().clone();
+        config.snakes = game
+            .alive_snakes()
+            .map(|snake| SnakeStart {
+                pos: snake.head_pos,
+                direction: snake.direction,
+            })
+            .collect();
+        config
+            .snakes
+            .sort_by_key(|start| (start.pos.1, start.pos.0));
+        config.walls = game.walls().to_vec();
+        config.food = game.food().iter().map(|food| food.pos).collect();
+
+        while config.start_length > 1 && config.validate().is_err() {
+            config.start_length -= 1;
+        }
+        config.validate()?;
+
+        Ok(Level { config })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+
+    pub fn into_game(self) -> Result<Game, Error> {
+        Game::from_config(self.config)
+    }
+
+    fn heading(c: char) -> Option<Direction> {
+        match c {
+            '^' => Some(Direction::Up),
+            '>' => Some(Direction::Right),
+            'v' => Some(Direction::Down),
+            '<' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    fn value<T: std::str::FromStr>(line: usize, column: usize, value: &str) -> Result<T, Error> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| Level::error(line, column, &format!("bad value '{}'", value.trim())))
+    }
+
+    fn error(line: usize, column: usize, message: &str) -> Error {
+        Error::Parse {
+            line: line + 1,
+            column: column + 1,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plain = GameConfig::default();
+        let food_amount = match self.config.food.len() {
+            0 => plain.food_amount,
+            food => food as u16,
+        };
+
+        if self.config.start_length != plain.start_length {
+            writeln!(f, "length = {}", self.config.start_length)?;
+        }
+        if self.config.wrap != plain.wrap {
+            writeln!(f, "wrap = {}", self.config.wrap)?;
+        }
+        if self.config.food_amount != food_amount {
+            writeln!(f, "food = {}", self.config.food_amount)?;
+        }
+        if self.config.food_weights == FoodWeights::mixed() {
+            writeln!(f, "food_kinds = mixed")?;
+        }
+
+        let width = self.config.width as usize;
+        let mut cells = vec!['.'; width * self.config.height as usize];
+        let index = |pos: &(u16, u16)| pos.1 as usize * width + pos.0 as usize;
+
+        for pos in self.config.food.iter() {
+            cells[index(pos)] = 'F';
+        }
+        for pos in self.config.walls.iter() {
+            cells[index(pos)] = '#';
+        }
+        for start in self.config.snakes.iter() {
+            cells[index(&start.pos)] = match start.direction {
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+                Direction::Right => '>',
+            };
+        }
+
+        for row in cells.chunks(width.max(1)) {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+mod test {
+    use crate::*;
+
+    const LEVEL: &str = "\
+#####
+#.F.#
+#>..#
+#####
+";
+
+    #[test]
+    fn should_parse_level() {
+        let level = Level::parse(LEVEL).unwrap();
+
+        assert_eq!((level.config.width, level.config.height), (5, 4));
+        assert_eq!(
+            level.config.snakes,
+            vec![SnakeStart {
+                pos: (1, 2),
+                direction: Direction::Right,
+            }]
+        );
+        assert_eq!(level.config.food, vec![(2, 1)]);
+        assert_eq!(level.config.walls.len(), 14);
+
+        let game = level.into_game().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (1, 2));
+        assert_eq!(game.food[0].pos, (2, 1));
+        assert!(game.is_wall((0, 0)));
+    }
+
+    #[test]
+    fn should_write_level_back() {
+        let game = Level::parse(LEVEL).unwrap().into_game().unwrap();
+        let level = Level::from_game(&game).unwrap();
+
+        assert_eq!(level.to_string(), LEVEL);
+        assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+    }
+
+    #[test]
+    fn should_write_settings_back() {
+        let mut config = Level::parse(".......\n.>..F..\n.......").unwrap().config;
+        config.start_length = 2;
+        config.food_weights = FoodWeights::mixed();
+        config.wrap = true;
+        config.food_amount = 3;
+
+        let game = Game::from_config(config.clone()).unwrap();
+        let text = Level::from_game(&game).unwrap().to_string();
+        assert_eq!(
+            text,
+            "length = 2\nwrap = true\nfood = 3\nfood_kinds = mixed\n.......\n.>..F..\n.......\n"
+        );
+        assert_eq!(Level::parse(&text).unwrap().config, config);
+    }
+
+    #[test]
+    fn should_write_the_game_as_it_is_now() {
+        let config = Level::parse("length = 2\n#......\n.>..F..\n.......")
+            .unwrap()
+            .config;
+        let mut game = Game::from_config(config).unwrap().with_seed(1);
+        game.start().unwrap();
+        for _ in 0..4 {
+            game.next().unwrap();
+        }
+        game.input(SnakeId(0), Direction::Down).unwrap();
+        game.next().unwrap();
+
+        let level = Level::from_game(&game).unwrap();
+        let snake = &game.snakes()[0];
+        assert_eq!(
+            level.config.snakes,
+            vec![SnakeStart {
+                pos: snake.head_pos,
+                direction: Direction::Down,
+            }]
+        );
+        assert!(!level.config.food.contains(&(4, 1)));
+        assert_eq!(
+            level.config.food,
+            game.food().iter().map(|food| food.pos).collect::<Vec<_>>()
+        );
+        assert_eq!(level.config.walls, vec![(0, 0)]);
+        assert_eq!(level.config.start_length, 2);
+        assert_eq!(Level::parse(&level.to_string()).unwrap(), level);
+    }
+
+    #[test]
+    fn should_not_write_a_finished_game() {
+        let mut game = Level::parse(">..").unwrap().into_game().unwrap();
+        game.start().unwrap();
+        while game.state() == GameState::Running {
+            game.next().unwrap();
+        }
+
+        assert!(matches!(
+            Level::from_game(&game),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn should_report_line_and_column() {
+        let error = |text: &str| match Level::parse(text) {
+            Err(Error::Parse { line, column, .. }) => (line, column),
+            _ => panic!("expected a parse error"),
+        };
+
+        assert_eq!(error("...\n.x.\n>.."), (2, 2));
+        assert_eq!(error("...\n..\n>.."), (2, 3));
+        assert_eq!(error(">..\n.<"), (2, 3));
+        assert_eq!(error("wrap = true\n>..\n.x."), (3, 2));
+        assert_eq!(error("length = two\n>.."), (1, 10));
+        assert_eq!(error("speed = 3\n>.."), (1, 1));
+        assert_eq!(error(">..\n\n..."), (2, 1));
+        assert_eq!(error("wrap = true\n>..\n   \n..."), (3, 1));
+
+        let level = Level::parse("wrap = true\n\n>..\n...").unwrap();
+        assert!(level.config.wrap);
+        assert_eq!((level.config.width, level.config.height), (3, 2));
+        let level = Level::parse("\n\n.>.\n...").unwrap();
+        assert_eq!(level.config.snakes[0].pos, (1, 0));
+        assert!(matches!(
+            Level::parse("...\n..."),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn should_parse_start_with_heading() {
+        let level = Level::parse("..S<\n#Sv.\n...").unwrap();
+
+        assert_eq!((level.config.width, level.config.height), (3, 3));
+        assert_eq!(
+            level.config.snakes,
+            vec![
+                SnakeStart {
+                    pos: (2, 0),
+                    direction: Direction::Left,
+                },
+                SnakeStart {
+                    pos: (1, 1),
+                    direction: Direction::Down,
+                },
+            ]
+        );
+        assert_eq!(level.config.walls, vec![(0, 1)]);
+        assert_eq!(
+            Level::parse("..S>\n...").unwrap().config.snakes,
+            Level::parse("..>\n...").unwrap().config.snakes
+        );
+
+        let error = |text: &str| match Level::parse(text) {
+            Err(Error::Parse { line, column, .. }) => (line, column),
+            _ => panic!("expected a parse error"),
+        };
+        assert_eq!(error("..S.\n...."), (1, 3));
+        assert_eq!(error("...\n..S"), (2, 3));
+        assert_eq!(error("S>..\n...."), (2, 4));
+    }
+
+    #[test]
+    fn should_parse_one_snake_per_start() {
+        let game = Level::parse(">...\n...<").unwrap().into_game().unwrap();
+
+        assert_eq!(game.snakes.len(), 2);
+        assert_eq!(game.snakes[1].id, SnakeId(1));
+        assert_eq!(game.snakes[1].head_pos, (3, 1));
+        assert_eq!(game.snakes[1].direction, Direction::Left);
+    }
+}
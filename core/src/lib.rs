@@ -1,8 +1,51 @@
 #![allow(warnings)]
 
-use rand::{rng, seq::IteratorRandom};
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+mod bot;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod config;
+mod controller;
+pub mod env;
+mod error;
+mod event;
+mod food;
+mod grid;
+mod level;
+mod power;
+mod replay;
+mod rewind;
+#[cfg(feature = "serde")]
+mod save;
+mod score;
+pub mod sim;
+mod speed;
+mod state;
+
+pub use bot::{Autopilot, Hamiltonian, Random};
+pub use config::{GameConfig, GameConfigBuilder, SnakeStart};
+pub use controller::{Controller, Keyboard, Remote, Scripted};
+pub use error::Error;
+pub use event::GameEvent;
+pub use food::{Food, FoodKind, FoodWeights};
+pub use level::Level;
+pub use power::{PowerUp, PowerUpConfig, PowerUpKind};
+pub use replay::{Recorder, Replay, ReplayPlayer, ReplayTick, REPLAY_EXTENSION, REPLAY_VERSION};
+#[cfg(feature = "serde")]
+pub use save::SAVE_VERSION;
+pub use score::{Score, ScoreConfig};
+pub use speed::{SpeedBasis, SpeedCurve};
+pub use state::GameState;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up,
     Down,
@@ -11,7 +54,18 @@ pub enum Direction {
 }
 
 impl Direction {
-    fn value(&self) -> (i32, i32) {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Direction::ALL.into_iter()
+    }
+
+    pub fn value(&self) -> (i32, i32) {
         match self {
             Direction::Up => (0, -1),
             Direction::Down => (0, 1),
@@ -20,454 +74,2427 @@ impl Direction {
         }
     }
 
-    fn get_valid_direction(&self, currrent: Direction) -> Vec<Direction> {
+    /// The direction a step of `(dx, dy)` goes in, if it is a single step.
+    pub fn from_delta(delta: (i32, i32)) -> Option<Direction> {
+        Direction::iter().find(|direction| direction.value() == delta)
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
         match self {
-            Direction::Up | Direction::Down => vec![Direction::Left, Direction::Right],
-            Direction::Left | Direction::Right => vec![Direction::Up, Direction::Down],
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
         }
     }
 
-    fn is_valid_direction(&self, direction: Direction) -> bool {
-        self.get_valid_direction(*self).contains(&direction)
+    pub fn turn_right(&self) -> Direction {
+        self.turn_left().opposite()
+    }
+
+    pub fn turn(&self, turn: Turn) -> Direction {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Straight => *self,
+            Turn::Right => self.turn_right(),
+        }
+    }
+
+    /// The directions a snake heading this way can turn to.
+    pub fn get_valid_direction(&self) -> [Direction; 2] {
+        [self.turn_left(), self.turn_right()]
+    }
+
+    pub fn is_valid_direction(&self, direction: Direction) -> bool {
+        self.get_valid_direction().contains(&direction)
+    }
+}
+
+/// A turn relative to where a snake is heading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnakeId(pub usize);
+
+impl std::fmt::Display for SnakeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "snake {}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeathCause {
+    Edge,
+    SelfCollision,
+    Wall,
+    HeadOn(SnakeId),
+    Body(SnakeId),
+}
+
+impl std::fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeathCause::Edge => write!(f, "hit the edge"),
+            DeathCause::SelfCollision => write!(f, "hit itself"),
+            DeathCause::Wall => write!(f, "hit a wall"),
+            DeathCause::HeadOn(id) => write!(f, "crashed head-on into {}", id),
+            DeathCause::Body(id) => write!(f, "hit {}", id),
+        }
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Snake {
-    pub body: Vec<(u16, u16)>,
+    pub id: SnakeId,
+    body: VecDeque<(u16, u16)>,
     pub direction: Direction,
-    pub next_direction: Direction,
     pub head_pos: (u16, u16),
     pub alive: bool,
-    grow: bool,
+    pub death_cause: Option<DeathCause>,
+    pub score: Score,
+    inputs: VecDeque<Direction>,
+    power_ups: Vec<PowerUp>,
+    grow: u32,
 }
 
 impl Snake {
     pub fn new(pos: (u16, u16)) -> Snake {
+        Snake::with_body(VecDeque::from([pos]), pos, Direction::Right)
+    }
+
+    /// A snake on `body`, tail first and head last.
+    pub fn from_body(body: Vec<(u16, u16)>, direction: Direction) -> Result<Snake, Error> {
+        let head_pos = *body
+            .last()
+            .ok_or_else(|| Error::InvalidConfig("snake body must not be empty".into()))?;
+
+        Ok(Snake::with_body(VecDeque::from(body), head_pos, direction))
+    }
+
+    fn with_body(body: VecDeque<(u16, u16)>, head_pos: (u16, u16), direction: Direction) -> Snake {
         Snake {
-            body: vec![(pos.0, pos.1)],
-            direction: Direction::Right,
-            next_direction: Direction::Right,
-            head_pos: (pos.0, pos.1),
+            id: SnakeId(0),
+            body,
+            direction,
+            head_pos,
             alive: true,
-            grow: false,
+            death_cause: None,
+            score: Score::default(),
+            inputs: VecDeque::new(),
+            power_ups: vec![],
+            grow: 0,
         }
     }
 
-    pub fn walk(&mut self) {
-        self.head_pos = self.next_pos().unwrap();
+    pub fn body(&self) -> &VecDeque<(u16, u16)> {
+        &self.body
+    }
+
+    pub fn next_direction(&self) -> Direction {
+        self.inputs.front().copied().unwrap_or(self.direction)
+    }
+
+    pub fn queue_direction(&mut self, direction: Direction, depth: usize) -> bool {
+        let last = self.inputs.back().copied().unwrap_or(self.direction);
+
+        if self.inputs.len() >= depth.max(1) || !last.is_valid_direction(direction) {
+            return false;
+        }
+
+        self.inputs.push_back(direction);
+
+        true
+    }
+
+    /// Queues `turn` from the last queued direction, so quick turns add up.
+    pub fn queue_turn(&mut self, turn: Turn, depth: usize) -> bool {
+        let last = self.inputs.back().copied().unwrap_or(self.direction);
+
+        self.queue_direction(last.turn(turn), depth)
+    }
+
+    fn turn(&mut self) {
+        if let Some(direction) = self.inputs.pop_front() {
+            self.direction = direction;
+        }
+    }
+
+    pub fn walk(&mut self) -> Result<Option<(u16, u16)>, Error> {
+        if !self.alive {
+            return Err(Error::DeadSnake);
+        }
+
+        self.walk_to(self.next_pos()?)
+    }
+
+    pub fn walk_to(&mut self, pos: (u16, u16)) -> Result<Option<(u16, u16)>, Error> {
+        if !self.alive {
+            return Err(Error::DeadSnake);
+        }
+
+        self.head_pos = pos;
+
+        self.body.push_back(pos);
+
+        if self.grow > 0 {
+            self.grow -= 1;
 
-        if self.grow {
-            self.grow = false;
-            self.body.push(self.head_pos);
+            Ok(None)
         } else {
-            self.body.remove(0);
-            self.body.push(self.head_pos);
+            Ok(self.body.pop_front())
         }
     }
 
-    pub fn next_pos(&self) -> Result<(u16, u16), &'static str> {
-        let (x, y) = self.next_direction.value();
+    pub fn next_pos(&self) -> Result<(u16, u16), Error> {
+        let (x, y) = self.next_direction().value();
+        let pos = (x + self.head_pos.0 as i32, y + self.head_pos.1 as i32);
 
-        let y = y + self.head_pos.1 as i32;
-        let y = u16::try_from(y).map_err(|_| {
-            if y < 0 {
-                "value is negative"
-            } else if y > u16::MAX as i32 {
-                "value too large"
-            } else {
-                "overflow in y"
-            }
-        })?;
-
-        let x = x + self.head_pos.0 as i32;
-        let x = u16::try_from(x).map_err(|_| {
-            if x < 0 {
-                "value is negative"
-            } else if x > u16::MAX as i32 {
-                "value too large"
-            } else {
-                "overflow in x"
-            }
-        })?;
+        if pos.0 < 0 || pos.1 < 0 {
+            return Err(Error::OutOfBounds { pos });
+        }
+
+        let x = u16::try_from(pos.0).map_err(|_| Error::Overflow { pos })?;
+        let y = u16::try_from(pos.1).map_err(|_| Error::Overflow { pos })?;
 
         Ok((x, y))
     }
 
     pub fn eat(&mut self) {
-        self.grow = true;
+        self.grow_by(1);
     }
 
-    pub fn self_collision(&self) -> bool {
-        let next_pos = self.next_pos().unwrap();
+    pub fn grow_by(&mut self, segments: u32) {
+        self.grow = self.grow.saturating_add(segments);
+    }
 
-        for i in 1..self.body.len() {
-            if next_pos == self.body[i] {
-                return true;
-            }
-        }
+    pub fn shrink(&mut self, segments: usize) -> Vec<(u16, u16)> {
+        let segments = segments.min(self.body.len() - 1);
 
-        false
+        self.body.drain(..segments).collect()
     }
-}
 
-#[derive(Clone, Copy)]
-pub struct Food {
-    pub pos: (u16, u16),
-}
+    pub fn power_ups(&self) -> &[PowerUp] {
+        &self.power_ups
+    }
 
-impl Food {
-    pub fn new(x: u16, y: u16) -> Food {
-        Food { pos: (x, y) }
+    pub fn has_power_up(&self, kind: PowerUpKind) -> bool {
+        self.power_ups.iter().any(|power_up| power_up.kind == kind)
     }
-}
 
-#[derive(Clone)]
-struct ConfigGame {
-    food_amount: u16,
-}
+    pub fn power_up(&mut self, kind: PowerUpKind, ticks: u32) {
+        self.power_ups.retain(|power_up| power_up.kind != kind);
+        self.power_ups.push(PowerUp { kind, ticks });
+    }
 
-impl Default for ConfigGame {
-    fn default() -> Self {
-        ConfigGame { food_amount: 1 }
+    fn tick_power_ups(&mut self) -> Vec<PowerUpKind> {
+        let mut ended = vec![];
+
+        self.power_ups.retain_mut(|power_up| {
+            power_up.ticks = power_up.ticks.saturating_sub(1);
+            if power_up.ticks == 0 {
+                ended.push(power_up.kind);
+            }
+
+            power_up.ticks > 0
+        });
+
+        ended
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
-    pub snake: Snake,
-    pub food: Vec<Food>,
-    initial_pos: (u16, u16),
-    config: ConfigGame,
-    height: u16,
-    width: u16,
+    snakes: Vec<Snake>,
+    food: Vec<Food>,
+    config: GameConfig,
+    state: GameState,
+    best_score: u32,
+    speed: u32,
+    speed_boost: i32,
+    tick: u64,
+    seed: u64,
+    rng: ChaCha8Rng,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: VecDeque<rewind::Tick>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    grid: grid::Grid,
 }
 
 impl Game {
-    pub fn new(pos: (u16, u16), width: u16, height: u16) -> Game {
-        Game {
-            snake: Snake::new(pos),
-            initial_pos: pos,
-            food: vec![],
-            height,
-            width,
-            config: ConfigGame {
-                food_amount: 1,
-                ..Default::default()
-            },
+    pub fn new(pos: (u16, u16), width: u16, height: u16) -> Result<Game, Error> {
+        Game::from_config(
+            GameConfig::builder()
+                .size(width, height)
+                .start_pos(pos)
+                .build(),
+        )
+    }
+
+    pub fn from_config(config: GameConfig) -> Result<Game, Error> {
+        config.validate()?;
+
+        let seed = rand::random();
+
+        let mut game = Game {
+            snakes: Game::start_snakes(&config)?,
+            food: config
+                .food
+                .iter()
+                .map(|pos| Food::new(pos.0, pos.1))
+                .collect(),
+            config,
+            state: GameState::Ready,
+            best_score: 0,
+            speed: 1,
+            speed_boost: 0,
+            tick: 0,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: VecDeque::new(),
+            grid: grid::Grid::default(),
+        };
+        game.rebuild_grid();
+        game.update_speed();
+
+        Ok(game)
+    }
+
+    fn start_snakes(config: &GameConfig) -> Result<Vec<Snake>, Error> {
+        let mut snakes = vec![];
+
+        for (i, start) in config.snakes.iter().enumerate() {
+            let mut snake = Snake::from_body(config.start_body(start)?, start.direction)?;
+            snake.id = SnakeId(i);
+            snakes.push(snake);
         }
+
+        Ok(snakes)
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Game {
+        self.set_seed(seed);
+        self
+    }
+
+    /// Reseeds the RNG from `seed`. The rewind history goes with the old one.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.history.clear();
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn width(&self) -> u16 {
+        self.config.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.config.height
     }
 
     pub fn reset(&mut self) {
-        self.snake = Snake::new(self.initial_pos);
-        self.food = vec![];
+        self.snakes =
+            Game::start_snakes(&self.config).expect("config was validated in Game::from_config");
+        self.food = self
+            .config
+            .food
+            .iter()
+            .map(|pos| Food::new(pos.0, pos.1))
+            .collect();
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.state = GameState::Ready;
+        self.tick = 0;
+        self.speed_boost = 0;
+        self.history.clear();
+        self.rebuild_grid();
+        self.update_speed();
     }
 
-    pub fn next(&mut self) -> bool {
-        self.snake.direction = self.snake.next_direction;
-        if (!self.snake.alive || !self.snake_inside() || self.snake.self_collision()) {
-            self.snake.alive = false;
-            return false;
+    pub(crate) fn rebuild_grid(&mut self) {
+        self.grid = grid::Grid::new(self.config.width, self.config.height);
+
+        for pos in self.config.walls.iter() {
+            self.grid.set_wall(*pos);
+        }
+        for (i, snake) in self.snakes.iter().enumerate() {
+            if snake.alive {
+                for pos in snake.body.iter() {
+                    self.grid.add_segment(*pos, i);
+                }
+            }
         }
+        for food in self.food.iter() {
+            self.grid.set_food(food.pos, true);
+        }
+    }
 
-        self.snake_collion_food();
-        self.generate_food();
-        self.snake.walk();
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
 
-        true
+    pub fn snake(&self, id: SnakeId) -> Option<&Snake> {
+        self.snakes.get(id.0)
     }
 
-    pub fn generate_food(&mut self) {
-        let food_amount = (self.config.food_amount as usize)
-            .checked_sub(self.food.len())
-            .unwrap_or(0);
+    pub fn set_snake(&mut self, id: SnakeId, mut snake: Snake) -> Result<(), Error> {
+        if id.0 >= self.snakes.len() {
+            return Err(Error::UnknownSnake(id));
+        }
+        if let Some(pos) = snake.body.iter().find(|pos| !self.grid.contains(**pos)) {
+            return Err(Error::OutOfBounds {
+                pos: (pos.0 as i32, pos.1 as i32),
+            });
+        }
+        for pos in snake.body.iter().filter(|_| snake.alive) {
+            if self.grid.is_wall(*pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "{} is on a wall at {:?}",
+                    id, pos
+                )));
+            }
+            if self.grid.segments(*pos) > 0 && self.grid.owner(*pos) != id.0 {
+                return Err(Error::InvalidConfig(format!("snakes overlap at {:?}", pos)));
+            }
+            if self.grid.is_food(*pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "food {:?} is not on a free cell",
+                    pos
+                )));
+            }
+        }
 
-        if food_amount == 0 {
-            return;
+        if self.snakes[id.0].alive {
+            for pos in self.snakes[id.0].body.iter() {
+                self.grid.remove_segment(*pos);
+            }
+        }
+        if snake.alive {
+            for pos in snake.body.iter() {
+                self.grid.add_segment(*pos, id.0);
+            }
         }
 
-        let mut possible_positions = Vec::new();
+        snake.id = id;
+        self.snakes[id.0] = snake;
+        self.history.clear();
+
+        Ok(())
+    }
+
+    pub fn food(&self) -> &[Food] {
+        &self.food
+    }
 
-        for x in 0..self.width {
-            for y in 0..self.height {
-                possible_positions.push((x, y));
+    pub fn set_food(&mut self, food: Vec<Food>) -> Result<(), Error> {
+        if let Some(food) = food.iter().find(|food| !self.grid.contains(food.pos)) {
+            return Err(Error::OutOfBounds {
+                pos: (food.pos.0 as i32, food.pos.1 as i32),
+            });
+        }
+        let mut placed = HashSet::with_capacity(food.len());
+        for pos in food.iter().map(|food| food.pos) {
+            if self.grid.is_wall(pos) || self.grid.segments(pos) > 0 || !placed.insert(pos) {
+                return Err(Error::InvalidConfig(format!(
+                    "food {:?} is not on a free cell",
+                    pos
+                )));
             }
         }
 
-        let pos = possible_positions
-            .iter()
-            .choose_multiple(&mut rand::thread_rng(), self.config.food_amount as usize);
+        for old in self.food.iter() {
+            self.grid.set_food(old.pos, false);
+        }
+        for new in food.iter() {
+            self.grid.set_food(new.pos, true);
+        }
+        self.food = food;
+        self.history.clear();
+
+        Ok(())
+    }
+
+    pub fn alive_snakes(&self) -> impl Iterator<Item = &Snake> {
+        self.snakes.iter().filter(|snake| snake.alive)
+    }
 
-        for pos in pos {
-            self.food.push(Food::new(pos.0, pos.1));
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn best_score(&self) -> u32 {
+        self.best_score
+    }
+
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        self.config.speed.interval(self.speed)
+    }
+
+    fn update_speed(&mut self) {
+        let start_length = self.config.start_length as usize;
+        let length = self
+            .alive_snakes()
+            .map(|snake| snake.body.len().saturating_sub(start_length))
+            .max()
+            .unwrap_or(0);
+        let score = self
+            .alive_snakes()
+            .map(|snake| snake.score.points)
+            .max()
+            .unwrap_or(0);
+
+        let level = self.config.speed.level(length as u32, score) as i64 + self.speed_boost as i64;
+
+        self.speed = level.clamp(1, self.config.speed.max_level.max(1) as i64) as u32;
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = replay::StateHasher::new();
+        hasher.write(&self.tick.to_le_bytes());
+        hasher.write(&[self.state as u8]);
+        hasher.write(&self.speed.to_le_bytes());
+
+        for snake in self.snakes.iter() {
+            hasher.write(&[snake.alive as u8, snake.direction as u8]);
+            hasher.write(&snake.score.points.to_le_bytes());
+            hasher.write(&snake.grow.to_le_bytes());
+            hasher.write(&(snake.body.len() as u64).to_le_bytes());
+            for pos in snake.body.iter() {
+                hasher.write(&pos.0.to_le_bytes());
+                hasher.write(&pos.1.to_le_bytes());
+            }
+            for power_up in snake.power_ups.iter() {
+                hasher.write(&[power_up.kind as u8]);
+                hasher.write(&power_up.ticks.to_le_bytes());
+            }
+        }
+
+        for food in self.food.iter() {
+            hasher.write(&food.pos.0.to_le_bytes());
+            hasher.write(&food.pos.1.to_le_bytes());
+            hasher.write(&[food.kind as u8]);
         }
+
+        hasher.finish()
     }
 
-    pub fn snake_inside(&self) -> bool {
-        let next_pos = match self.snake.next_pos() {
-            Ok(pos) => pos,
-            Err(_) => return false,
-        };
+    pub fn start(&mut self) -> Result<(), Error> {
+        self.transition(&[GameState::Ready], GameState::Running)
+    }
+
+    pub fn pause(&mut self) -> Result<(), Error> {
+        self.transition(&[GameState::Running], GameState::Paused)
+    }
+
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.transition(&[GameState::Paused], GameState::Running)
+    }
+
+    pub fn game_over(&mut self) -> Result<(), Error> {
+        self.transition(
+            &[GameState::Running, GameState::Paused],
+            GameState::GameOver,
+        )
+    }
+
+    fn transition(&mut self, from: &[GameState], to: GameState) -> Result<(), Error> {
+        if !from.contains(&self.state) {
+            return Err(Error::InvalidTransition {
+                from: self.state,
+                to,
+            });
+        }
+
+        self.state = to;
+
+        Ok(())
+    }
 
-        next_pos.0 < self.width && next_pos.1 < self.height
+    pub fn next(&mut self) -> Result<Vec<GameEvent>, Error> {
+        let mut rng = self.rng.clone();
+        let result = self.step(&mut rng, true);
+        self.rng = rng;
+
+        result
+    }
+
+    /// Like [`Game::next`], but draws from `rng` instead of the game's own.
+    /// The rewind history can't restore an outside RNG, so it is dropped and
+    /// the tick can't be rewound.
+    pub fn next_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<Vec<GameEvent>, Error> {
+        self.step(rng, false)
     }
 
-    pub fn snake_collion_food(&mut self) {
+    fn step<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        rewind: bool,
+    ) -> Result<Vec<GameEvent>, Error> {
+        if self.state != GameState::Running {
+            return Ok(vec![]);
+        }
+
+        if self.alive_snakes().next().is_none() {
+            return Err(Error::DeadSnake);
+        }
+
+        match rewind {
+            true => self.begin_tick(),
+            false => self.history.clear(),
+        }
+        self.tick += 1;
+        let mut targets: Vec<Option<Result<(u16, u16), DeathCause>>> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                if !snake.alive {
+                    return None;
+                }
+
+                Some(match self.next_pos(snake.id) {
+                    Ok(pos) if self.is_wall(pos) => Err(DeathCause::Wall),
+                    Ok(pos) => Ok(pos),
+                    Err(_) => Err(DeathCause::Edge),
+                })
+            })
+            .collect();
+        for snake in self.snakes.iter_mut().filter(|snake| snake.alive) {
+            snake.turn();
+        }
+
+        let mut grows: Vec<bool> = targets
+            .iter()
+            .zip(self.snakes.iter())
+            .map(|(target, snake)| {
+                snake.grow > 0
+                    || matches!(target, Some(Ok(pos))
+                        if self.food_at(*pos).is_some_and(|food| food.kind.growth() > 0))
+            })
+            .collect();
+
+        let mut events = vec![];
+
+        let mut causes = self.collisions(&targets, &grows);
+        while let Some(i) = (0..self.snakes.len())
+            .find(|i| causes[*i].is_some() && self.snakes[*i].has_power_up(PowerUpKind::Shield))
+        {
+            let snake = &mut self.snakes[i];
+            snake
+                .power_ups
+                .retain(|power_up| power_up.kind != PowerUpKind::Shield);
+            events.push(GameEvent::Absorbed {
+                id: snake.id,
+                cause: causes[i].expect("only snakes about to die use their shield"),
+            });
+
+            targets[i] = None;
+            grows[i] = true;
+            causes = self.collisions(&targets, &grows);
+        }
+
+        for snake in self.snakes.iter_mut().filter(|snake| snake.alive) {
+            for kind in snake.tick_power_ups() {
+                events.push(GameEvent::PowerUpEnded { id: snake.id, kind });
+            }
+        }
+
+        for (i, target) in targets.into_iter().enumerate() {
+            let id = self.snakes[i].id;
+
+            if let Some(cause) = causes[i] {
+                for pos in self.snakes[i].body.iter() {
+                    self.grid.remove_segment(*pos);
+                }
+                self.snakes[i].alive = false;
+                self.snakes[i].death_cause = Some(cause);
+                events.push(GameEvent::Died { id, cause });
+                continue;
+            }
+
+            let pos = match target {
+                Some(Ok(pos)) => pos,
+                _ => continue,
+            };
+
+            let eaten = self.take_food(pos);
+            if let Some(Food { kind, .. }) = eaten {
+                events.push(GameEvent::AteFood { id, pos, kind });
+
+                let config = &self.config.score;
+                let snake = &mut self.snakes[i];
+                snake.grow_by(kind.growth());
+                if kind == FoodKind::Poison {
+                    snake.score.break_combo();
+                } else {
+                    snake.score.award(
+                        kind.points(config.food_points),
+                        self.tick,
+                        self.speed,
+                        config,
+                    );
+                }
+                if let Some(power_up) = kind.power_up() {
+                    snake.power_up(power_up, self.config.power_ups.duration);
+                }
+                self.best_score = self.best_score.max(snake.score.points);
+                self.speed_boost += kind.speed();
+            }
+
+            let vacated = self.snakes[i].walk_to(pos)?;
+            self.record_walk(i, vacated);
+            self.grid.add_segment(pos, i);
+            if let Some(vacated) = vacated {
+                self.grid.remove_segment(vacated);
+            }
+            events.push(GameEvent::Moved {
+                id,
+                head: pos,
+                vacated,
+            });
+            if vacated.is_none() {
+                events.push(GameEvent::Grew { id });
+            }
+
+            if let Some(Food { kind, .. }) = eaten {
+                let snake = &mut self.snakes[i];
+                for vacated in snake.shrink(kind.shrink(snake.body.len())) {
+                    self.record_shrink(i, vacated);
+                    self.grid.remove_segment(vacated);
+                    events.push(GameEvent::Shrank { id, vacated });
+                }
+            }
+        }
+
+        if self.alive_snakes().next().is_none() {
+            self.state = GameState::GameOver;
+            return Ok(events);
+        }
+        self.update_speed();
+        self.pull_food(&mut events);
+
         let food_amount = self.food.len();
-        let next_pos = self.snake.next_pos().unwrap();
+        self.record_spawn();
+        match self.generate_food_with(rng) {
+            Ok(()) => (),
+            Err(Error::BoardFull) if self.food.is_empty() => {
+                self.state = GameState::Won;
+                events.push(GameEvent::Won);
+            }
+            Err(Error::BoardFull) => (),
+            Err(e) => return Err(e),
+        }
 
-        self.food = self
-            .food
+        for food in self.food[food_amount..].iter() {
+            events.push(GameEvent::FoodSpawned {
+                pos: food.pos,
+                kind: food.kind,
+            });
+        }
+
+        Ok(events)
+    }
+
+    fn collisions(
+        &self,
+        targets: &[Option<Result<(u16, u16), DeathCause>>],
+        grows: &[bool],
+    ) -> Vec<Option<DeathCause>> {
+        targets
             .iter()
-            .filter(|f| f.pos != next_pos)
-            .cloned()
+            .enumerate()
+            .map(|(i, target)| match target {
+                Some(Ok(pos)) => self.collision(i, *pos, targets, grows),
+                Some(Err(cause)) => Some(*cause),
+                None => None,
+            })
+            .collect()
+    }
+
+    fn collision(
+        &self,
+        i: usize,
+        pos: (u16, u16),
+        targets: &[Option<Result<(u16, u16), DeathCause>>],
+        grows: &[bool],
+    ) -> Option<DeathCause> {
+        let snake = &self.snakes[i];
+
+        for (j, other) in self.snakes.iter().enumerate() {
+            if j == i || !other.alive {
+                continue;
+            }
+
+            let other_target = match targets[j] {
+                Some(Ok(target)) => Some(target),
+                _ => None,
+            };
+
+            if other_target == Some(pos)
+                || (other_target == Some(snake.head_pos) && pos == other.head_pos)
+            {
+                return Some(DeathCause::HeadOn(other.id));
+            }
+        }
+
+        let segments = self.grid.segments(pos);
+        if segments == 0 {
+            return None;
+        }
+
+        let owner = self.grid.owner(pos);
+        let tail_moves = !grows[owner] && self.snakes[owner].body.front() == Some(&pos);
+        if segments == 1 && tail_moves {
+            return None;
+        }
+
+        if owner != i {
+            Some(DeathCause::Body(self.snakes[owner].id))
+        } else if snake.has_power_up(PowerUpKind::Ghost) {
+            None
+        } else {
+            Some(DeathCause::SelfCollision)
+        }
+    }
+
+    fn pull_food(&mut self, events: &mut Vec<GameEvent>) {
+        let radius = self.config.power_ups.magnet_radius as i32;
+        let heads: Vec<(u16, u16)> = self
+            .alive_snakes()
+            .filter(|snake| snake.has_power_up(PowerUpKind::Magnet))
+            .map(|snake| snake.head_pos)
             .collect();
 
-        let food_amount_eat = (food_amount - self.food.len()) as u16;
-        for _ in 0..food_amount_eat {
-            self.snake.eat();
+        for head in heads {
+            for i in 0..self.food.len() {
+                let from = self.food[i].pos;
+                let dx = self.axis_delta(from.0, head.0, self.config.width);
+                let dy = self.axis_delta(from.1, head.1, self.config.height);
+
+                if dx.abs().max(dy.abs()) > radius {
+                    continue;
+                }
+
+                let to = (
+                    (from.0 as i32 + dx.signum()).rem_euclid(self.config.width as i32) as u16,
+                    (from.1 as i32 + dy.signum()).rem_euclid(self.config.height as i32) as u16,
+                );
+                if self.is_occupied(to) {
+                    continue;
+                }
+
+                self.record_pull(i, from);
+                self.grid.set_food(from, false);
+                self.grid.set_food(to, true);
+                self.food[i].pos = to;
+                events.push(GameEvent::FoodMoved { from, to });
+            }
+        }
+    }
+
+    // The steps from `from` to `to` on an axis `size` cells long, the short
+    // way round when the board wraps.
+    fn axis_delta(&self, from: u16, to: u16, size: u16) -> i32 {
+        let delta = to as i32 - from as i32;
+
+        if self.config.wrap && delta.abs() * 2 > size as i32 {
+            delta - delta.signum() * size as i32
+        } else {
+            delta
         }
     }
 
-    pub fn input(&mut self, direction: Direction) {
-        if !self.snake.direction.is_valid_direction(direction) {
-            return;
+    pub fn generate_food(&mut self) -> Result<(), Error> {
+        let mut rng = self.rng.clone();
+        let result = self.generate_food_with(&mut rng);
+        self.rng = rng;
+
+        result
+    }
+
+    pub fn generate_food_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(), Error> {
+        let food_amount = (self.config.food_amount as usize).saturating_sub(self.food.len());
+
+        if food_amount == 0 {
+            return Ok(());
+        }
+
+        if self.grid.free_len() == 0 {
+            return Err(Error::BoardFull);
         }
 
-        self.snake.next_direction = direction
+        for _ in 0..food_amount.min(self.grid.free_len()) {
+            let pos = self.grid.free_at(rng.random_range(0..self.grid.free_len()));
+            let kind = self.config.food_weights.choose(rng);
+            self.grid.set_food(pos, true);
+            self.food.push(Food::with_kind(pos, kind));
+        }
+
+        Ok(())
+    }
+
+    pub fn free_positions(&self) -> Vec<(u16, u16)> {
+        self.grid.free_cells().collect()
+    }
+
+    pub fn is_occupied(&self, pos: (u16, u16)) -> bool {
+        self.grid.contains(pos) && !self.grid.is_free(pos)
+    }
+
+    pub fn is_food(&self, pos: (u16, u16)) -> bool {
+        self.grid.is_food(pos)
+    }
+
+    pub fn food_at(&self, pos: (u16, u16)) -> Option<&Food> {
+        if !self.grid.is_food(pos) {
+            return None;
+        }
+
+        self.food.iter().find(|f| f.pos == pos)
+    }
+
+    fn take_food(&mut self, pos: (u16, u16)) -> Option<Food> {
+        if !self.grid.is_food(pos) {
+            return None;
+        }
+
+        let i = self.food.iter().position(|f| f.pos == pos)?;
+        self.grid.set_food(pos, false);
+        let food = self.food.remove(i);
+        self.record_eaten(i, food);
+
+        Some(food)
+    }
+
+    pub fn walls(&self) -> &[(u16, u16)] {
+        &self.config.walls
+    }
+
+    pub fn is_wall(&self, pos: (u16, u16)) -> bool {
+        self.grid.is_wall(pos)
+    }
+
+    pub fn next_pos(&self, id: SnakeId) -> Result<(u16, u16), Error> {
+        let snake = self.snake(id).ok_or(Error::UnknownSnake(id))?;
+
+        if !self.config.wrap {
+            let pos = snake.next_pos()?;
+
+            if !self.config.contains(pos) {
+                return Err(Error::OutOfBounds {
+                    pos: (pos.0 as i32, pos.1 as i32),
+                });
+            }
+
+            return Ok(pos);
+        }
+
+        let (x, y) = snake.next_direction().value();
+        let x = (snake.head_pos.0 as i32 + x).rem_euclid(self.config.width as i32);
+        let y = (snake.head_pos.1 as i32 + y).rem_euclid(self.config.height as i32);
+
+        Ok((x as u16, y as u16))
+    }
+
+    /// Whether `id` runs into its own body on its next move.
+    pub fn self_collision(&self, id: SnakeId) -> Result<bool, Error> {
+        let snake = self.snake(id).ok_or(Error::UnknownSnake(id))?;
+        if snake.has_power_up(PowerUpKind::Ghost) {
+            return Ok(false);
+        }
+
+        Ok(self.collides_with(id, self.next_pos(id)?))
+    }
+
+    /// Whether `pos` is on the body of `id`, leaving out the tail that moves
+    /// away on the next tick.
+    pub fn collides_with(&self, id: SnakeId, pos: (u16, u16)) -> bool {
+        let Some(snake) = self.snake(id) else {
+            return false;
+        };
+        if !snake.alive || !self.grid.contains(pos) || self.grid.owner(pos) != id.0 {
+            return false;
+        }
+
+        let tail = (snake.body.front() == Some(&pos)) as u16;
+        self.grid.segments(pos) > tail
+    }
+
+    pub fn input(&mut self, id: SnakeId, direction: Direction) -> Result<(), Error> {
+        let depth = self.config.input_buffer;
+        let snake = self.snakes.get_mut(id.0).ok_or(Error::UnknownSnake(id))?;
+        snake.queue_direction(direction, depth);
+
+        Ok(())
+    }
+
+    pub fn input_turn(&mut self, id: SnakeId, turn: Turn) -> Result<(), Error> {
+        let depth = self.config.input_buffer;
+        let snake = self.snakes.get_mut(id.0).ok_or(Error::UnknownSnake(id))?;
+        snake.queue_turn(turn, depth);
+
+        Ok(())
     }
 }
 
 mod test {
     use crate::*;
 
+    #[test]
+    fn snake_should_need_a_body() {
+        assert!(matches!(
+            Snake::from_body(vec![], Direction::Right),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let snake = Snake::from_body(vec![(1, 0), (2, 0)], Direction::Right).unwrap();
+        assert_eq!(snake.head_pos, (2, 0));
+    }
+
     #[test]
     fn snake_should_walk() {
-        let mut game = Game {
-            initial_pos: (3, 5),
-            snake: Snake {
-                head_pos: (3, 5),
-                body: vec![(1, 5), (2, 5), (3, 5)],
-                direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
-            },
-            food: vec![],
-            height: 11,
-            width: 11,
-            config: ConfigGame {
-                food_amount: 0,
-                ..Default::default()
-            },
-        };
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(11, 11)
+                .start_pos((3, 5))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(1, 5), (2, 5), (3, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (4, 5));
+        assert_eq!(game.snakes[0].body, vec![(2, 5), (3, 5), (4, 5)]);
+        assert_eq!(game.snakes[0].grow, 0);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (5, 5));
+        assert_eq!(game.snakes[0].body, vec![(3, 5), (4, 5), (5, 5)]);
+        assert_eq!(game.snakes[0].grow, 0);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (6, 5));
+        assert_eq!(game.snakes[0].body, vec![(4, 5), (5, 5), (6, 5)]);
+        assert_eq!(game.snakes[0].grow, 0);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (7, 5));
+        assert_eq!(game.snakes[0].body, vec![(5, 5), (6, 5), (7, 5)]);
+        assert_eq!(game.snakes[0].grow, 0);
+        assert_eq!(game.snakes[0].alive, true);
+    }
+
+    #[test]
+    fn snake_should_eat() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(11, 11)
+                .start_pos((3, 5))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(3, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(5, 5)]).unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (4, 5));
+        assert_eq!(game.snakes[0].body, vec![(4, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+
+        assert_eq!(game.snakes[0].head_pos, (5, 5));
+        assert_eq!(game.snakes[0].body, vec![(4, 5), (5, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (6, 5));
+        assert_eq!(game.snakes[0].body, vec![(5, 5), (6, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (7, 5));
+        assert_eq!(game.snakes[0].body, vec![(6, 5), (7, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (8, 5));
+        assert_eq!(game.snakes[0].body, vec![(7, 5), (8, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+
+        let mut food = game.food().to_vec();
+        food.push(Food::new(9, 5));
+        game.set_food(food).unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (9, 5));
+        assert_eq!(game.snakes[0].body, vec![(7, 5), (8, 5), (9, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+    }
+
+    #[test]
+    fn snake_should_die() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(6, 6)
+                .start_pos((4, 3))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(4, 3)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.next().unwrap();
+        assert!(game.snakes[0].alive);
+
+        game.next().unwrap();
+        assert!(!game.snakes[0].alive);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (4, 5));
-        assert_eq!(game.snake.body, vec![(2, 5), (3, 5), (4, 5)]);
-        assert_eq!(game.snake.grow, false);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn snake_should_change_direction() {
+        let mut game =
+            Game::from_config(GameConfig::builder().size(11, 11).start_pos((5, 5)).build())
+                .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(5, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.input(SnakeId(0), Direction::Left).unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Right);
+        assert_eq!(game.snakes[0].next_direction(), Direction::Right);
+
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Up);
+        assert_eq!(game.snakes[0].direction, Direction::Right);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        assert_eq!(game.snakes[0].head_pos, (5, 4));
+        assert!(game.snakes[0].alive);
+
+        game.input(SnakeId(0), Direction::Left).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Left);
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        assert_eq!(game.snakes[0].head_pos, (4, 4));
+        assert!(game.snakes[0].alive);
+
+        game.input(SnakeId(0), Direction::Down).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Down);
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Down);
+        assert_eq!(game.snakes[0].head_pos, (4, 5));
+        assert!(game.snakes[0].alive);
+
+        game.input(SnakeId(0), Direction::Right).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Right);
+        assert_eq!(game.snakes[0].direction, Direction::Down);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Right);
+        assert_eq!(game.snakes[0].head_pos, (5, 5));
+        assert!(game.snakes[0].alive);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (5, 5));
-        assert_eq!(game.snake.body, vec![(3, 5), (4, 5), (5, 5)]);
-        assert_eq!(game.snake.grow, false);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn direction_should_turn() {
+        for direction in Direction::iter() {
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.turn_left().turn_left(), direction.opposite());
+            assert_eq!(direction.turn(Turn::Straight), direction);
+            assert_eq!(Direction::from_delta(direction.value()), Some(direction));
+            assert!(!direction.is_valid_direction(direction));
+            assert!(!direction.is_valid_direction(direction.opposite()));
+        }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (6, 5));
-        assert_eq!(game.snake.body, vec![(4, 5), (5, 5), (6, 5)]);
-        assert_eq!(game.snake.grow, false);
-        assert_eq!(game.snake.alive, true);
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Right.turn_right(), Direction::Down);
+        assert_eq!(
+            Direction::Left.get_valid_direction(),
+            [Direction::Down, Direction::Up]
+        );
+        assert_eq!(Direction::from_delta((1, 1)), None);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (7, 5));
-        assert_eq!(game.snake.body, vec![(5, 5), (6, 5), (7, 5)]);
-        assert_eq!(game.snake.grow, false);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn snake_should_take_relative_turns() {
+        let mut game =
+            Game::from_config(GameConfig::builder().size(11, 11).start_pos((5, 5)).build())
+                .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(5, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.input_turn(SnakeId(0), Turn::Straight).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Right);
+
+        game.input_turn(SnakeId(0), Turn::Left).unwrap();
+        game.input_turn(SnakeId(0), Turn::Left).unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        assert_eq!(game.snakes[0].head_pos, (4, 4));
+
+        game.input_turn(SnakeId(0), Turn::Right).unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        assert_eq!(game.snakes[0].head_pos, (4, 3));
     }
 
     #[test]
-    fn snake_should_eat() {
-        let mut game = Game {
-            initial_pos: (3, 5),
-            snake: Snake {
-                head_pos: (3, 5),
-                body: vec![(3, 5)],
-                direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
-            },
-            food: vec![Food::new(5, 5)],
-            height: 11,
-            width: 11,
-            config: ConfigGame { food_amount: 0 },
+    fn snake_should_self_collision() {
+        let mut game =
+            Game::from_config(GameConfig::builder().size(11, 11).start_pos((5, 5)).build())
+                .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(
+                vec![(6, 5), (5, 4), (4, 4), (4, 5), (5, 5)],
+                Direction::Right,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert!(game.collides_with(SnakeId(0), (5, 4)));
+        assert!(!game.collides_with(SnakeId(0), (6, 5)));
+        assert!(!game.collides_with(SnakeId(0), (6, 4)));
+
+        assert!(!game.self_collision(SnakeId(0)).unwrap());
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        assert!(game.self_collision(SnakeId(0)).unwrap());
+
+        game.next().unwrap();
+        assert!(!game.snakes[0].alive);
+    }
+
+    #[test]
+    fn snake_should_die_at_left_edge() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(6, 6)
+                .start_pos((0, 3))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(0, 3)], Direction::Left).unwrap(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            game.snakes[0].next_pos(),
+            Err(Error::OutOfBounds { pos: (-1, 3) })
+        ));
+        assert!(matches!(
+            game.self_collision(SnakeId(0)),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        assert_eq!(
+            game.next().unwrap(),
+            vec![GameEvent::Died {
+                id: SnakeId(0),
+                cause: DeathCause::Edge
+            }]
+        );
+        assert!(!game.snakes[0].alive);
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_eq!(game.next().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn should_generate_new_food() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(11, 11)
+                .start_pos((5, 5))
+                .food_amount(3)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(5, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(6, 5)]).unwrap();
+
+        assert_eq!(game.food.len(), 1);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (6, 5));
+        assert_eq!(game.snakes[0].body, vec![(5, 5), (6, 5)]);
+        assert_eq!(game.snakes[0].alive, true);
+        assert_eq!(game.food.len(), 3);
+    }
+
+    #[test]
+    fn same_seed_should_place_same_food() {
+        let positions = |seed: u64| {
+            let mut game = Game::new((5, 5), 11, 11).unwrap().with_seed(seed);
+            game.start().unwrap();
+            let mut food = vec![];
+
+            for _ in 0..3 {
+                game.next().unwrap();
+                food.extend(game.food.iter().map(|f| f.pos));
+            }
+
+            food
         };
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (4, 5));
-        assert_eq!(game.snake.body, vec![(4, 5)]);
-        assert_eq!(game.snake.alive, true);
+        assert_eq!(positions(42), positions(42));
+        assert_eq!(Game::new((5, 5), 11, 11).unwrap().with_seed(42).seed(), 42);
+    }
 
-        game.next();
+    #[test]
+    fn reset_should_replay_the_seed() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap().with_seed(7);
+        game.start().unwrap();
+        game.next().unwrap();
+        let food = game.food[0].pos;
+
+        game.reset();
+        game.start().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.food[0].pos, food);
+        assert_eq!(game.seed(), 7);
+    }
 
-        assert_eq!(game.snake.head_pos, (5, 5));
-        assert_eq!(game.snake.body, vec![(4, 5), (5, 5)]);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn should_generate_food_with_injected_rng() {
+        let mut a = Game::new((5, 5), 11, 11).unwrap();
+        let mut b = Game::new((5, 5), 11, 11).unwrap();
+
+        a.generate_food_with(&mut ChaCha8Rng::seed_from_u64(3))
+            .unwrap();
+        b.generate_food_with(&mut ChaCha8Rng::seed_from_u64(3))
+            .unwrap();
+        assert_eq!(a.food[0].pos, b.food[0].pos);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (6, 5));
-        assert_eq!(game.snake.body, vec![(5, 5), (6, 5)]);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn should_top_up_food_on_free_cells() {
+        let mut game = Game::new((0, 0), 3, 2).unwrap().with_seed(1);
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(0, 0), (1, 0), (2, 0), (2, 1)], Direction::Down).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(1, 1)]).unwrap();
+        game.config.food_amount = 3;
+
+        game.generate_food().unwrap();
+        assert_eq!(game.food.len(), 2);
+        assert!(game.food.iter().any(|f| f.pos == (0, 1)));
+
+        assert!(matches!(game.generate_food(), Err(Error::BoardFull)));
+        assert_eq!(game.food.len(), 2);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (7, 5));
-        assert_eq!(game.snake.body, vec![(6, 5), (7, 5)]);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn should_not_add_more_than_missing_food() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        game.start().unwrap();
+        game.set_food(vec![Food::new(1, 1), Food::new(2, 2)])
+            .unwrap();
+        game.config.food_amount = 3;
+
+        game.generate_food().unwrap();
+        assert_eq!(game.food.len(), 3);
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (8, 5));
-        assert_eq!(game.snake.body, vec![(7, 5), (8, 5)]);
-        assert_eq!(game.snake.alive, true);
+        for food in game.food.iter() {
+            assert!(!game.snakes[0].body.contains(&food.pos));
+        }
+    }
 
-        game.food.push(Food::new(9, 5));
+    #[test]
+    fn should_build_game_from_config() {
+        let config = GameConfig::builder()
+            .size(8, 6)
+            .start_pos((4, 2))
+            .start_length(3)
+            .start_direction(Direction::Down)
+            .food_amount(2)
+            .build();
+        let game = Game::from_config(config.clone()).unwrap();
+
+        assert_eq!(game.config(), &config);
+        assert_eq!((game.width(), game.height()), (8, 6));
+        assert_eq!(game.snakes[0].body, vec![(4, 0), (4, 1), (4, 2)]);
+        assert_eq!(game.snakes[0].head_pos, (4, 2));
+        assert_eq!(game.snakes[0].direction, Direction::Down);
+    }
 
-        game.next();
-        assert_eq!(game.snake.head_pos, (9, 5));
-        assert_eq!(game.snake.body, vec![(7, 5), (8, 5), (9, 5)]);
-        assert_eq!(game.snake.alive, true);
+    #[test]
+    fn should_reject_invalid_config() {
+        let invalid = [
+            GameConfig::builder().size(0, 5).build(),
+            GameConfig::builder().size(5, 5).start_pos((5, 2)).build(),
+            GameConfig::builder().start_length(0).build(),
+            GameConfig::builder()
+                .start_pos((1, 1))
+                .start_length(3)
+                .build(),
+            GameConfig::builder().size(2, 2).food_amount(4).build(),
+        ];
+
+        for config in invalid {
+            assert!(matches!(
+                Game::from_config(config),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
     }
 
     #[test]
-    fn snake_should_die() {
-        let mut game = Game {
-            initial_pos: (4, 3),
-            snake: Snake {
-                head_pos: (4, 3),
-                body: vec![(4, 3)],
-                direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
-            },
-            food: vec![],
-            height: 6,
-            width: 6,
-            config: ConfigGame { food_amount: 0 },
-        };
+    fn snake_should_wrap_around_edges() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(4, 3)
+                .start_pos((3, 1))
+                .food_amount(0)
+                .wrap(true)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (0, 1));
+        assert!(game.snakes[0].alive);
+
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (0, 2));
+
+        game.input(SnakeId(0), Direction::Left).unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (3, 2));
+        assert!(game.snakes[0].alive);
+    }
 
-        game.next();
-        assert!(game.snake.alive);
+    #[test]
+    fn snake_should_eat_across_edge() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(5, 5)
+                .start_pos((0, 2))
+                .food_amount(0)
+                .wrap(true)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(1, 2), (0, 2)], Direction::Left).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(4, 2)]).unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].body, vec![(1, 2), (0, 2), (4, 2)]);
+        assert!(game.food.is_empty());
+    }
 
-        game.next();
-        assert!(!game.snake.alive);
+    #[test]
+    fn snake_should_collide_with_itself_across_edge() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(4, 4)
+                .start_pos((0, 1))
+                .food_amount(0)
+                .wrap(true)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(
+                vec![(2, 1), (3, 1), (3, 0), (0, 0), (0, 1)],
+                Direction::Down,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        game.input(SnakeId(0), Direction::Left).unwrap();
+
+        assert_eq!(
+            game.next().unwrap(),
+            vec![GameEvent::Died {
+                id: SnakeId(0),
+                cause: DeathCause::SelfCollision
+            }]
+        );
+        assert!(!game.snakes[0].alive);
     }
 
     #[test]
-    fn snake_should_change_direction() {
-        let mut game = Game {
-            initial_pos: (5, 5),
-            snake: Snake {
-                head_pos: (5, 5),
-                body: vec![(5, 5)],
+    fn snake_should_die_on_wall() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(6, 6)
+                .start_pos((2, 2))
+                .food_amount(0)
+                .wall((4, 2))
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        game.next().unwrap();
+        assert!(game.snakes[0].alive);
+        assert_eq!(game.snakes[0].death_cause, None);
+
+        assert_eq!(
+            game.next().unwrap(),
+            vec![GameEvent::Died {
+                id: SnakeId(0),
+                cause: DeathCause::Wall
+            }]
+        );
+        assert!(!game.snakes[0].alive);
+        assert_eq!(game.snakes[0].death_cause, Some(DeathCause::Wall));
+        assert_eq!(game.snakes[0].head_pos, (3, 2));
+    }
+
+    #[test]
+    fn should_report_death_cause() {
+        let mut game = Game::new((4, 3), 6, 6).unwrap();
+        game.start().unwrap();
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].death_cause, Some(DeathCause::Edge));
+
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(
+                vec![(6, 5), (5, 4), (4, 4), (4, 5), (5, 5)],
+                Direction::Right,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].death_cause, Some(DeathCause::SelfCollision));
+    }
+
+    #[test]
+    fn food_should_not_spawn_on_walls() {
+        let mut walls = vec![];
+        for x in 0..3 {
+            for y in 0..3 {
+                if (x, y) != (0, 0) && (x, y) != (2, 2) {
+                    walls.push((x, y));
+                }
+            }
+        }
+
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(3, 3)
+                .start_pos((0, 0))
+                .walls(walls)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        game.generate_food().unwrap();
+        assert_eq!(game.food[0].pos, (2, 2));
+    }
+
+    #[test]
+    fn should_reject_walls_on_snake() {
+        let config = GameConfig::builder().start_pos((5, 5)).wall((5, 5)).build();
+
+        assert!(matches!(
+            Game::from_config(config),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_duplicate_walls() {
+        let config = GameConfig::builder()
+            .size(2, 1)
+            .start_pos((0, 0))
+            .food_amount(0)
+            .walls(vec![(1, 0), (1, 0), (1, 0)])
+            .build();
+
+        assert!(matches!(
+            Game::from_config(config),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn next_should_report_events() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(6, 1)
+                .start_pos((1, 0))
+                .start_length(2)
+                .food_amount(1)
+                .build(),
+        )
+        .unwrap()
+        .with_seed(3);
+        game.start().unwrap();
+        game.set_food(vec![Food::new(2, 0)]).unwrap();
+
+        let events = game.next().unwrap();
+        assert_eq!(
+            events[0],
+            GameEvent::AteFood {
+                id: SnakeId(0),
+                pos: (2, 0),
+                kind: FoodKind::Normal,
+            }
+        );
+        assert_eq!(
+            events[1],
+            GameEvent::Moved {
+                id: SnakeId(0),
+                head: (2, 0),
+                vacated: None,
+            }
+        );
+        assert_eq!(events[2], GameEvent::Grew { id: SnakeId(0) });
+        assert_eq!(
+            events[3],
+            GameEvent::FoodSpawned {
+                pos: game.food[0].pos,
+                kind: game.food[0].kind,
+            }
+        );
+        assert_eq!(events.len(), 4);
+
+        game.set_food(vec![Food::new(5, 0)]).unwrap();
+        assert_eq!(
+            game.next().unwrap(),
+            vec![GameEvent::Moved {
+                id: SnakeId(0),
+                head: (3, 0),
+                vacated: Some((0, 0)),
+            }]
+        );
+    }
+
+    #[test]
+    fn filling_the_board_should_win() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(3, 1)
+                .start_pos((1, 0))
+                .start_length(2)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_food(vec![Food::new(2, 0)]).unwrap();
+
+        let events = game.next().unwrap();
+        assert_eq!(events.last(), Some(&GameEvent::Won));
+        assert_eq!(game.state(), GameState::Won);
+        assert_eq!(game.snakes[0].body, vec![(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn should_follow_state_transitions() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        assert_eq!(game.state(), GameState::Ready);
+        assert_eq!(game.next().unwrap(), vec![]);
+        assert_eq!(game.snakes[0].head_pos, (5, 5));
+        assert!(matches!(
+            game.pause(),
+            Err(Error::InvalidTransition {
+                from: GameState::Ready,
+                to: GameState::Paused,
+            })
+        ));
+
+        game.start().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (6, 5));
+
+        game.pause().unwrap();
+        assert_eq!(game.next().unwrap(), vec![]);
+        assert_eq!(game.snakes[0].head_pos, (6, 5));
+        assert!(game.start().is_err());
+
+        game.resume().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (7, 5));
+
+        game.game_over().unwrap();
+        assert_eq!(game.state(), GameState::GameOver);
+        assert!(game.resume().is_err());
+        assert_eq!(game.next().unwrap(), vec![]);
+
+        game.reset();
+        assert_eq!(game.state(), GameState::Ready);
+        assert_eq!(game.snakes[0].head_pos, (5, 5));
+    }
+
+    #[test]
+    fn should_score_food_with_combo() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(20, 1)
+                .start_pos((0, 0))
+                .food_amount(0)
+                .score(ScoreConfig {
+                    food_points: 10,
+                    combo_window: 3,
+                    max_combo: 2,
+                })
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.set_food(vec![
+            Food::new(1, 0),
+            Food::new(3, 0),
+            Food::new(5, 0),
+            Food::new(10, 0),
+        ])
+        .unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].score.points, 10);
+        assert_eq!(game.snakes[0].score.combo, 1);
+
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].score.points, 30);
+        assert_eq!(game.snakes[0].score.combo, 2);
+
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].score.points, 50);
+        assert_eq!(game.snakes[0].score.combo, 2);
+
+        for _ in 0..5 {
+            game.next().unwrap();
+        }
+        assert_eq!(game.snakes[0].score.points, 60);
+        assert_eq!(game.snakes[0].score.combo, 1);
+        assert_eq!(game.best_score(), 60);
+    }
+
+    #[test]
+    fn combo_should_restart_after_a_later_tick() {
+        let config = ScoreConfig::default();
+        let mut score = Score::default();
+        score.award(10, 9, 1, &config);
+        score.award(10, 10, 1, &config);
+        assert_eq!(score.combo, 2);
+
+        score.award(10, 1, 1, &config);
+        assert_eq!(score.combo, 1);
+        assert_eq!(score.points, 10 + 20 + 10);
+    }
+
+    #[test]
+    fn best_score_should_survive_reset() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        game.start().unwrap();
+        game.set_food(vec![Food::new(6, 5)]).unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].score.points, 10);
+
+        game.reset();
+        assert_eq!(game.snakes[0].score.points, 0);
+        assert_eq!(game.best_score(), 10);
+    }
+
+    fn versus(width: u16, height: u16, a: SnakeStart, b: SnakeStart) -> Game {
+        let mut config = GameConfig::builder()
+            .size(width, height)
+            .food_amount(0)
+            .build();
+        config.snakes = vec![a, b];
+
+        let mut game = Game::from_config(config).unwrap();
+        game.start().unwrap();
+        game
+    }
+
+    #[test]
+    fn snakes_should_die_on_same_cell() {
+        let mut game = versus(
+            5,
+            1,
+            SnakeStart {
+                pos: (1, 0),
                 direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
             },
-            food: vec![],
-            height: 11,
-            width: 11,
-            config: ConfigGame::default(),
-        };
-
-        game.input(Direction::Left);
-        assert_eq!(game.snake.direction, Direction::Right);
-        assert_eq!(game.snake.next_direction, Direction::Right);
-
-        game.input(Direction::Up);
-        assert_eq!(game.snake.next_direction, Direction::Up);
-        assert_eq!(game.snake.direction, Direction::Right);
-        game.next();
-        assert_eq!(game.snake.direction, Direction::Up);
-        assert_eq!(game.snake.head_pos, (5, 4));
-        assert!(game.snake.alive);
-
-        game.input(Direction::Left);
-        assert_eq!(game.snake.next_direction, Direction::Left);
-        assert_eq!(game.snake.direction, Direction::Up);
-        game.next();
-        assert_eq!(game.snake.direction, Direction::Left);
-        assert_eq!(game.snake.head_pos, (4, 4));
-        assert!(game.snake.alive);
-
-        game.input(Direction::Down);
-        assert_eq!(game.snake.next_direction, Direction::Down);
-        assert_eq!(game.snake.direction, Direction::Left);
-        game.next();
-        assert_eq!(game.snake.direction, Direction::Down);
-        assert_eq!(game.snake.head_pos, (4, 5));
-        assert!(game.snake.alive);
-
-        game.input(Direction::Right);
-        assert_eq!(game.snake.next_direction, Direction::Right);
-        assert_eq!(game.snake.direction, Direction::Down);
-        game.next();
-        assert_eq!(game.snake.direction, Direction::Right);
-        assert_eq!(game.snake.head_pos, (5, 5));
-        assert!(game.snake.alive);
+            SnakeStart {
+                pos: (3, 0),
+                direction: Direction::Left,
+            },
+        );
+
+        let events = game.next().unwrap();
+        assert!(events.contains(&GameEvent::Died {
+            id: SnakeId(0),
+            cause: DeathCause::HeadOn(SnakeId(1)),
+        }));
+        assert!(events.contains(&GameEvent::Died {
+            id: SnakeId(1),
+            cause: DeathCause::HeadOn(SnakeId(0)),
+        }));
+        assert_eq!(game.state(), GameState::GameOver);
     }
 
     #[test]
-    fn snake_should_self_collision() {
-        let mut game = Game {
-            initial_pos: (5, 5),
-            snake: Snake {
-                head_pos: (5, 5),
-                body: vec![(6, 5), (5, 4), (4, 4), (4, 5), (5, 5)],
+    fn snakes_should_die_head_on() {
+        let mut game = versus(
+            4,
+            1,
+            SnakeStart {
+                pos: (1, 0),
                 direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
             },
-            food: vec![],
-            height: 11,
-            width: 11,
-            config: ConfigGame::default(),
-        };
+            SnakeStart {
+                pos: (2, 0),
+                direction: Direction::Left,
+            },
+        );
+
+        game.next().unwrap();
+        assert_eq!(
+            game.snakes[0].death_cause,
+            Some(DeathCause::HeadOn(SnakeId(1)))
+        );
+        assert_eq!(
+            game.snakes[1].death_cause,
+            Some(DeathCause::HeadOn(SnakeId(0)))
+        );
+    }
 
-        assert!(!game.snake.self_collision());
-        game.input(Direction::Up);
-        assert!(game.snake.self_collision());
+    #[test]
+    fn snake_should_die_on_other_body() {
+        let mut game = versus(
+            6,
+            6,
+            SnakeStart {
+                pos: (1, 2),
+                direction: Direction::Right,
+            },
+            SnakeStart {
+                pos: (2, 4),
+                direction: Direction::Up,
+            },
+        );
+        game.set_snake(
+            SnakeId(1),
+            Snake::from_body(vec![(2, 4), (2, 3), (2, 2), (2, 1)], Direction::Up).unwrap(),
+        )
+        .unwrap();
+
+        let events = game.next().unwrap();
+        assert_eq!(
+            events[0],
+            GameEvent::Died {
+                id: SnakeId(0),
+                cause: DeathCause::Body(SnakeId(1)),
+            }
+        );
+        assert!(game.snakes[1].alive);
+        assert_eq!(game.snakes[1].head_pos, (2, 0));
+        assert_eq!(game.state(), GameState::Running);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[1].death_cause, Some(DeathCause::Edge));
+        assert_eq!(game.state(), GameState::GameOver);
+    }
 
-        game.next();
-        assert!(!game.snake.alive);
+    #[test]
+    fn snake_may_follow_another_tail() {
+        let mut game = versus(
+            6,
+            1,
+            SnakeStart {
+                pos: (1, 0),
+                direction: Direction::Right,
+            },
+            SnakeStart {
+                pos: (3, 0),
+                direction: Direction::Right,
+            },
+        );
+        game.set_snake(
+            SnakeId(1),
+            Snake::from_body(vec![(2, 0), (3, 0)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.next().unwrap();
+        assert!(game.snakes[0].alive);
+        assert_eq!(game.snakes[0].body, vec![(2, 0)]);
+
+        game.set_food(vec![Food::new(5, 0)]).unwrap();
+        game.next().unwrap();
+        assert_eq!(
+            game.snakes[0].death_cause,
+            Some(DeathCause::Body(SnakeId(1)))
+        );
     }
 
     #[test]
-    fn should_generate_new_food() {
-        let mut game = Game {
-            initial_pos: (5, 5),
-            snake: Snake {
-                head_pos: (5, 5),
-                body: vec![(5, 5)],
+    fn snakes_should_have_own_input_and_score() {
+        let mut game = versus(
+            8,
+            8,
+            SnakeStart {
+                pos: (1, 1),
                 direction: Direction::Right,
-                next_direction: Direction::Right,
-                alive: true,
-                grow: false,
             },
-            food: vec![Food::new(6, 5)],
-            height: 11,
-            width: 11,
-            config: ConfigGame { food_amount: 3 },
+            SnakeStart {
+                pos: (6, 6),
+                direction: Direction::Left,
+            },
+        );
+        game.set_food(vec![Food::new(6, 5)]).unwrap();
+
+        game.input(SnakeId(1), Direction::Up).unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (2, 1));
+        assert_eq!(game.snakes[1].head_pos, (6, 5));
+        assert_eq!(game.snakes[0].score.points, 0);
+        assert_eq!(game.snakes[1].score.points, 10);
+        assert_eq!(game.best_score(), 10);
+
+        assert!(matches!(
+            game.input(SnakeId(2), Direction::Up),
+            Err(Error::UnknownSnake(SnakeId(2)))
+        ));
+    }
+
+    #[test]
+    fn snake_should_buffer_quick_turns() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        game.start().unwrap();
+
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        game.input(SnakeId(0), Direction::Left).unwrap();
+        assert_eq!(game.snakes[0].next_direction(), Direction::Up);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (5, 4));
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (4, 4));
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (3, 4));
+    }
+
+    #[test]
+    fn snake_should_reject_quick_u_turn() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap();
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(4, 5), (5, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+
+        game.input(SnakeId(0), Direction::Left).unwrap();
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        game.input(SnakeId(0), Direction::Down).unwrap();
+        game.input(SnakeId(0), Direction::Left).unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (5, 4));
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (4, 4));
+        assert!(game.snakes[0].alive);
+    }
+
+    #[test]
+    fn input_queue_should_respect_depth() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(11, 11)
+                .start_pos((5, 5))
+                .input_buffer(1)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        game.input(SnakeId(0), Direction::Up).unwrap();
+        game.input(SnakeId(0), Direction::Left).unwrap();
+
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].head_pos, (5, 3));
+    }
+
+    #[test]
+    fn speed_should_follow_length() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(20, 1)
+                .start_pos((0, 0))
+                .food((1, 0))
+                .food((2, 0))
+                .speed(SpeedCurve {
+                    every: 2,
+                    ..SpeedCurve::normal()
+                })
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+
+        assert_eq!(game.speed(), 1);
+        assert_eq!(game.tick_interval(), Duration::from_millis(200));
+
+        game.next().unwrap();
+        assert_eq!(game.speed(), 1);
+        game.next().unwrap();
+        assert_eq!(game.speed(), 2);
+        assert_eq!(game.tick_interval(), Duration::from_millis(190));
+
+        game.reset();
+        assert_eq!(game.speed(), 1);
+    }
+
+    #[test]
+    fn speed_curve_should_clamp() {
+        let curve = SpeedCurve {
+            basis: SpeedBasis::Score,
+            every: 10,
+            ..SpeedCurve::insane()
         };
 
-        assert_eq!(game.food.len(), 1);
-        game.next();
-        assert_eq!(game.snake.head_pos, (6, 5));
-        assert_eq!(game.snake.body, vec![(5, 5), (6, 5)]);
-        assert_eq!(game.snake.alive, true);
-        assert_eq!(game.food.len(), 3);
+        assert_eq!(curve.level(100, 0), 1);
+        assert_eq!(curve.level(0, 35), 4);
+        assert_eq!(curve.level(0, 10_000), curve.max_level);
+        assert_eq!(curve.interval(curve.max_level), Duration::from_millis(40));
+        assert_eq!(
+            SpeedCurve {
+                basis: SpeedBasis::Level(3),
+                ..SpeedCurve::easy()
+            }
+            .level(0, 0),
+            3
+        );
+    }
+
+    #[test]
+    fn speed_presets_should_parse() {
+        assert_eq!("easy".parse::<SpeedCurve>().unwrap(), SpeedCurve::easy());
+        assert_eq!(
+            "insane".parse::<SpeedCurve>().unwrap(),
+            SpeedCurve::insane()
+        );
+        assert!("fast".parse::<SpeedCurve>().is_err());
+        assert!(matches!(
+            Game::from_config(
+                GameConfig::builder()
+                    .speed(SpeedCurve {
+                        every: 0,
+                        ..SpeedCurve::normal()
+                    })
+                    .build()
+            ),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    fn row(x: u16) -> Game {
+        Game::from_config(
+            GameConfig::builder()
+                .size(10, 1)
+                .start_pos((x, 0))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn golden_food_should_grow_and_score_more() {
+        let mut game = row(0);
+        game.start().unwrap();
+        game.set_food(vec![Food::with_kind((1, 0), FoodKind::Golden)])
+            .unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].score.points, 50);
+        game.next().unwrap();
+        game.next().unwrap();
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].body, vec![(1, 0), (2, 0), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn poison_should_remove_segments() {
+        let mut game = row(5);
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(1, 0), (2, 0), (3, 0), (4, 0)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::with_kind((5, 0), FoodKind::Poison)])
+            .unwrap();
+
+        let events = game.next().unwrap();
+        assert_eq!(game.snakes[0].body, vec![(4, 0), (5, 0)]);
+        assert_eq!(game.snakes[0].score.points, 0);
+        assert!(events.contains(&GameEvent::Shrank {
+            id: SnakeId(0),
+            vacated: (2, 0),
+        }));
+        assert!(events.contains(&GameEvent::Shrank {
+            id: SnakeId(0),
+            vacated: (3, 0),
+        }));
+    }
+
+    #[test]
+    fn shrink_should_keep_the_head() {
+        let mut game = row(5);
+        game.start().unwrap();
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(3, 0), (4, 0)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![
+            Food::with_kind((5, 0), FoodKind::Shrink),
+            Food::with_kind((6, 0), FoodKind::Poison),
+        ])
+        .unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].body, vec![(5, 0)]);
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].body, vec![(6, 0)]);
+        assert!(game.snakes[0].alive);
+    }
+
+    #[test]
+    fn speed_food_should_change_speed() {
+        let mut game = row(0);
+        game.start().unwrap();
+        game.set_food(vec![
+            Food::with_kind((1, 0), FoodKind::SpeedUp),
+            Food::with_kind((2, 0), FoodKind::SpeedUp),
+            Food::with_kind((3, 0), FoodKind::SlowDown),
+        ])
+        .unwrap();
+
+        game.next().unwrap();
+        assert_eq!(game.speed(), 2);
+        game.next().unwrap();
+        assert_eq!(game.speed(), 3);
+        game.next().unwrap();
+        assert_eq!(game.speed(), 2);
+
+        game.reset();
+        assert_eq!(game.speed(), 1);
+    }
+
+    #[test]
+    fn food_should_spawn_by_weight() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .food_amount(5)
+                .food_weights(FoodWeights::only(FoodKind::Golden))
+                .build(),
+        )
+        .unwrap();
+
+        game.generate_food().unwrap();
+        assert!(game.food.iter().all(|food| food.kind == FoodKind::Golden));
+        assert!(matches!(
+            Game::from_config(
+                GameConfig::builder()
+                    .food_weights(FoodWeights {
+                        normal: 0,
+                        ..FoodWeights::only(FoodKind::Normal)
+                    })
+                    .build()
+            ),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn ghost_should_pass_through_itself() {
+        let mut game = row(5);
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(
+                vec![(3, 0), (4, 0), (5, 0), (6, 0), (7, 0)],
+                Direction::Left,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        game.snakes[0].power_up(PowerUpKind::Ghost, 1);
+        game.start().unwrap();
+
+        assert!(!game.self_collision(SnakeId(0)).unwrap());
+        let events = game.next().unwrap();
+        assert!(game.snakes[0].alive);
+        assert!(events.contains(&GameEvent::PowerUpEnded {
+            id: SnakeId(0),
+            kind: PowerUpKind::Ghost,
+        }));
+        assert!(game.snakes[0].power_ups().is_empty());
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].death_cause, Some(DeathCause::SelfCollision));
+    }
+
+    #[test]
+    fn shield_should_absorb_one_collision() {
+        let mut game = row(7);
+        game.start().unwrap();
+        game.set_food(vec![Food::with_kind((8, 0), FoodKind::Shield)])
+            .unwrap();
+        game.next().unwrap();
+        assert!(game.snakes[0].has_power_up(PowerUpKind::Shield));
+
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(9, 0)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.snakes[0].power_up(PowerUpKind::Shield, 10);
+
+        let events = game.next().unwrap();
+        assert_eq!(
+            events,
+            vec![GameEvent::Absorbed {
+                id: SnakeId(0),
+                cause: DeathCause::Edge,
+            }]
+        );
+        assert!(game.snakes[0].alive);
+        assert_eq!(game.snakes[0].head_pos, (9, 0));
+
+        game.next().unwrap();
+        assert_eq!(game.snakes[0].death_cause, Some(DeathCause::Edge));
+    }
+
+    #[test]
+    fn magnet_should_pull_food() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(10, 10)
+                .start_pos((0, 0))
+                .food_amount(0)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.snakes[0].power_up(PowerUpKind::Magnet, 10);
+        game.set_food(vec![Food::new(4, 3), Food::new(9, 9)])
+            .unwrap();
+
+        let events = game.next().unwrap();
+        assert!(events.contains(&GameEvent::FoodMoved {
+            from: (4, 3),
+            to: (3, 2),
+        }));
+        assert_eq!(game.food[1].pos, (9, 9));
+
+        game.next().unwrap();
+        assert_eq!(game.food[0].pos, (2, 1));
+    }
+
+    #[test]
+    fn magnet_should_pull_food_across_edge() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(10, 10)
+                .start_pos((0, 0))
+                .food_amount(0)
+                .wrap(true)
+                .build(),
+        )
+        .unwrap();
+        game.start().unwrap();
+        game.snakes[0].power_up(PowerUpKind::Magnet, 10);
+        game.set_food(vec![Food::new(8, 9)]).unwrap();
+
+        let events = game.next().unwrap();
+        assert!(events.contains(&GameEvent::FoodMoved {
+            from: (8, 9),
+            to: (9, 0),
+        }));
+    }
+
+    #[test]
+    fn grid_should_match_the_board() {
+        let directions = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+
+        for seed in 0..20 {
+            let mut game = Game::from_config(
+                GameConfig::builder()
+                    .size(8, 8)
+                    .snake((1, 6), Direction::Up)
+                    .food_amount(4)
+                    .wall((4, 4))
+                    .wrap(seed % 2 == 0)
+                    .build(),
+            )
+            .unwrap()
+            .with_seed(seed);
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            game.start().unwrap();
+
+            while game.state() == GameState::Running {
+                for snake in game.snakes.clone().iter() {
+                    let direction = directions[rng.random_range(0..4)];
+                    game.input(snake.id, direction).unwrap();
+                }
+                game.next().unwrap();
+
+                for x in 0..8 {
+                    for y in 0..8 {
+                        let pos = (x, y);
+                        let occupied = game.alive_snakes().any(|snake| snake.body.contains(&pos))
+                            || game.food.iter().any(|food| food.pos == pos)
+                            || game.config.walls.contains(&pos);
+
+                        assert_eq!(game.is_occupied(pos), occupied, "seed {} {:?}", seed, pos);
+                    }
+                }
+                assert_eq!(
+                    game.free_positions().len(),
+                    64 - (0..64).filter(|i| game.is_occupied((i % 8, i / 8))).count()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_reject_food_and_snakes_on_taken_cells() {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(8, 8)
+                .start_pos((1, 1))
+                .snake((1, 5), Direction::Right)
+                .wall((4, 4))
+                .build(),
+        )
+        .unwrap();
+        game.set_food(vec![Food::new(6, 6)]).unwrap();
+
+        let rejected = |result| matches!(result, Err(Error::InvalidConfig(_)));
+        assert!(rejected(
+            game.set_food(vec![Food::new(2, 2), Food::new(2, 2)])
+        ));
+        assert!(rejected(game.set_food(vec![Food::new(4, 4)])));
+        assert!(rejected(game.set_food(vec![Food::new(1, 5)])));
+        assert_eq!(game.food[0].pos, (6, 6));
+
+        let snake = |body| Snake::from_body(body, Direction::Right).unwrap();
+        assert!(rejected(
+            game.set_snake(SnakeId(0), snake(vec![(3, 4), (4, 4)]))
+        ));
+        assert!(rejected(
+            game.set_snake(SnakeId(0), snake(vec![(0, 5), (1, 5)]))
+        ));
+        assert!(rejected(
+            game.set_snake(SnakeId(0), snake(vec![(5, 6), (6, 6)]))
+        ));
+        assert_eq!(game.snakes[0].head_pos, (1, 1));
+
+        game.set_snake(SnakeId(0), snake(vec![(1, 1), (2, 1)]))
+            .unwrap();
+        assert!(game.is_occupied((2, 1)));
     }
 }
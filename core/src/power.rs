@@ -0,0 +1,42 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerUpKind {
+    Ghost,
+    Shield,
+    Magnet,
+}
+
+impl fmt::Display for PowerUpKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerUpKind::Ghost => write!(f, "ghost"),
+            PowerUpKind::Shield => write!(f, "shield"),
+            PowerUpKind::Magnet => write!(f, "magnet"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub ticks: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerUpConfig {
+    pub duration: u32,
+    pub magnet_radius: u16,
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        PowerUpConfig {
+            duration: 40,
+            magnet_radius: 4,
+        }
+    }
+}
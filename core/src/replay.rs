@@ -0,0 +1,266 @@
+//! Replays keep the seed, the config and the input given before each tick,
+//! along with a hash of the game after that tick. Playing one back rebuilds
+//! the game from scratch and stops at the first tick whose hash differs.
+
+use crate::{Direction, Error, Game, GameConfig, GameEvent, SnakeId};
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_EXTENSION: &str = "snakereplay";
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayTick {
+    pub inputs: Vec<(SnakeId, Direction)>,
+    pub hash: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn game(&self) -> Result<Game, Error> {
+        let mut game = Game::from_config(self.config.clone())?.with_seed(self.seed);
+        game.start()?;
+
+        Ok(game)
+    }
+
+    pub fn player(&self) -> ReplayPlayer<'_> {
+        ReplayPlayer {
+            replay: self,
+            tick: 0,
+        }
+    }
+
+    pub fn verify(&self) -> Result<Game, Error> {
+        let mut game = self.game()?;
+        let mut player = self.player();
+        while player.step(&mut game).transpose()?.is_some() {}
+
+        Ok(game)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Replay {
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let json = serde_json::to_string(self).map_err(|e| Error::Save(e.to_string()))?;
+        std::fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Replay, Error> {
+        let replay: Replay = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::Save(e.to_string()))?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(Error::UnsupportedVersion(replay.version));
+        }
+
+        Ok(replay)
+    }
+}
+
+/// Steps a game built with [`Replay::game`] through the recorded ticks.
+pub struct ReplayPlayer<'a> {
+    replay: &'a Replay,
+    tick: usize,
+}
+
+impl ReplayPlayer<'_> {
+    pub fn is_done(&self) -> bool {
+        self.tick >= self.replay.ticks.len()
+    }
+
+    pub fn step(&mut self, game: &mut Game) -> Option<Result<Vec<GameEvent>, Error>> {
+        let recorded = self.replay.ticks.get(self.tick)?;
+        self.tick += 1;
+
+        Some(ReplayPlayer::apply(game, recorded))
+    }
+
+    fn apply(game: &mut Game, recorded: &ReplayTick) -> Result<Vec<GameEvent>, Error> {
+        for (id, direction) in recorded.inputs.iter() {
+            game.input(*id, *direction)?;
+        }
+
+        let events = game.next()?;
+        let hash = game.state_hash();
+        if hash != recorded.hash {
+            return Err(Error::ReplayMismatch {
+                tick: game.tick(),
+                expected: recorded.hash,
+                found: hash,
+            });
+        }
+
+        Ok(events)
+    }
+}
+
+/// Records a game from its first tick, so start it from a fresh or reset game.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    replay: Replay,
+    inputs: Vec<(SnakeId, Direction)>,
+}
+
+impl Recorder {
+    pub fn new(game: &Game) -> Recorder {
+        Recorder {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                seed: game.seed(),
+                config: game.config().clone(),
+                ticks: vec![],
+            },
+            inputs: vec![],
+        }
+    }
+
+    pub fn input(
+        &mut self,
+        game: &mut Game,
+        id: SnakeId,
+        direction: Direction,
+    ) -> Result<(), Error> {
+        game.input(id, direction)?;
+        self.inputs.push((id, direction));
+
+        Ok(())
+    }
+
+    pub fn next(&mut self, game: &mut Game) -> Result<Vec<GameEvent>, Error> {
+        let tick = game.tick();
+        let events = game.next()?;
+
+        if game.tick() != tick {
+            self.replay.ticks.push(ReplayTick {
+                inputs: std::mem::take(&mut self.inputs),
+                hash: game.state_hash(),
+            });
+        }
+
+        Ok(events)
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// FNV-1a, written out so hashes stay the same across platforms and releases.
+pub(crate) struct StateHasher(u64);
+
+impl StateHasher {
+    pub(crate) fn new() -> StateHasher {
+        StateHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+mod test {
+    use crate::*;
+
+    fn recorded() -> Replay {
+        let mut game = Game::from_config(GameConfig::builder().size(12, 12).food_amount(4).build())
+            .unwrap()
+            .with_seed(11);
+        let mut recorder = Recorder::new(&game);
+        game.start().unwrap();
+
+        for (i, direction) in [
+            Direction::Down,
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+        ]
+        .iter()
+        .cycle()
+        .take(16)
+        .enumerate()
+        {
+            if i % 3 == 0 {
+                recorder.input(&mut game, SnakeId(0), *direction).unwrap();
+            }
+            let _ = recorder.next(&mut game);
+        }
+
+        recorder.finish()
+    }
+
+    #[test]
+    fn replay_should_rebuild_the_game() {
+        let replay = recorded();
+        assert!(!replay.ticks.is_empty());
+
+        let game = replay.verify().unwrap();
+        assert_eq!(game.state_hash(), replay.ticks.last().unwrap().hash);
+        assert_eq!(game.tick(), replay.ticks.len() as u64);
+    }
+
+    #[test]
+    fn replay_should_catch_a_changed_tick() {
+        let mut replay = recorded();
+        replay.ticks[2].hash ^= 1;
+
+        let mut game = replay.game().unwrap();
+        let mut player = replay.player();
+        assert!(player.step(&mut game).unwrap().is_ok());
+        assert!(player.step(&mut game).unwrap().is_ok());
+        assert!(matches!(
+            player.step(&mut game).unwrap(),
+            Err(Error::ReplayMismatch { tick: 3, .. })
+        ));
+        assert!(matches!(
+            replay.verify(),
+            Err(Error::ReplayMismatch { tick: 3, .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn replay_should_round_trip_through_a_file() {
+        let replay = recorded();
+        let path = std::env::temp_dir().join(format!("test.{}", REPLAY_EXTENSION));
+
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, replay);
+        loaded.verify().unwrap();
+    }
+
+    #[test]
+    fn state_hash_should_follow_the_game() {
+        let mut game = Game::new((5, 5), 11, 11).unwrap().with_seed(1);
+        let other = game.clone();
+        assert_eq!(game.state_hash(), other.state_hash());
+
+        game.start().unwrap();
+        game.next().unwrap();
+        assert_ne!(game.state_hash(), other.state_hash());
+    }
+}
@@ -0,0 +1,347 @@
+//! A short history of ticks to step back through. Each tick keeps what it
+//! changed rather than a copy of the game, so recording one costs the same
+//! however long the snakes grow.
+
+use std::collections::VecDeque;
+
+use crate::{DeathCause, Direction, Food, Game, GameState, PowerUp, Score};
+
+/// What a tick changed. Fields it may overwrite are kept as they were before
+/// it, cells and food only as what was added or taken away.
+#[derive(Clone)]
+pub(crate) struct Tick {
+    snakes: Vec<SnakeTick>,
+    eaten: Vec<(usize, Food)>,
+    pulled: Vec<(usize, (u16, u16))>,
+    food_len: usize,
+    state: GameState,
+    best_score: u32,
+    speed: u32,
+    speed_boost: i32,
+    tick: u64,
+    word_pos: u128,
+}
+
+#[derive(Clone)]
+struct SnakeTick {
+    direction: Direction,
+    head_pos: (u16, u16),
+    alive: bool,
+    death_cause: Option<DeathCause>,
+    score: Score,
+    inputs: VecDeque<Direction>,
+    power_ups: Vec<PowerUp>,
+    grow: u32,
+    moved: bool,
+    vacated: Option<(u16, u16)>,
+    shrank: Vec<(u16, u16)>,
+}
+
+impl Game {
+    pub(crate) fn begin_tick(&mut self) {
+        if self.config.rewind == 0 {
+            return;
+        }
+
+        while self.history.len() >= self.config.rewind {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(Tick {
+            snakes: self
+                .snakes
+                .iter()
+                .map(|snake| SnakeTick {
+                    direction: snake.direction,
+                    head_pos: snake.head_pos,
+                    alive: snake.alive,
+                    death_cause: snake.death_cause,
+                    score: snake.score.clone(),
+                    inputs: snake.inputs.clone(),
+                    power_ups: snake.power_ups.clone(),
+                    grow: snake.grow,
+                    moved: false,
+                    vacated: None,
+                    shrank: vec![],
+                })
+                .collect(),
+            eaten: vec![],
+            pulled: vec![],
+            food_len: self.food.len(),
+            state: self.state,
+            best_score: self.best_score,
+            speed: self.speed,
+            speed_boost: self.speed_boost,
+            tick: self.tick,
+            word_pos: self.rng.get_word_pos(),
+        });
+    }
+
+    fn current_tick(&mut self) -> Option<&mut Tick> {
+        match self.config.rewind {
+            0 => None,
+            _ => self.history.back_mut(),
+        }
+    }
+
+    pub(crate) fn record_walk(&mut self, i: usize, vacated: Option<(u16, u16)>) {
+        if let Some(snake) = self.current_tick().map(|tick| &mut tick.snakes[i]) {
+            snake.moved = true;
+            snake.vacated = vacated;
+        }
+    }
+
+    pub(crate) fn record_shrink(&mut self, i: usize, vacated: (u16, u16)) {
+        if let Some(tick) = self.current_tick() {
+            tick.snakes[i].shrank.push(vacated);
+        }
+    }
+
+    pub(crate) fn record_eaten(&mut self, index: usize, food: Food) {
+        if let Some(tick) = self.current_tick() {
+            tick.eaten.push((index, food));
+        }
+    }
+
+    pub(crate) fn record_pull(&mut self, index: usize, from: (u16, u16)) {
+        if let Some(tick) = self.current_tick() {
+            tick.pulled.push((index, from));
+        }
+    }
+
+    /// Food spawned from here on is taken away again on rewind.
+    pub(crate) fn record_spawn(&mut self) {
+        let food_len = self.food.len();
+        if let Some(tick) = self.current_tick() {
+            tick.food_len = food_len;
+        }
+    }
+
+    // Undoes a tick in the opposite order of `step`. The grid is left
+    // for the caller to rebuild once it is done undoing.
+    fn undo(&mut self, tick: Tick) {
+        self.food.truncate(tick.food_len);
+        for (index, from) in tick.pulled.into_iter().rev() {
+            self.food[index].pos = from;
+        }
+        for (index, food) in tick.eaten.into_iter().rev() {
+            self.food.insert(index, food);
+        }
+
+        for (snake, before) in self.snakes.iter_mut().zip(tick.snakes) {
+            if before.moved {
+                snake.body.pop_back();
+            }
+            for pos in before.shrank.into_iter().rev() {
+                snake.body.push_front(pos);
+            }
+            if let Some(pos) = before.vacated {
+                snake.body.push_front(pos);
+            }
+
+            snake.direction = before.direction;
+            snake.head_pos = before.head_pos;
+            snake.alive = before.alive;
+            snake.death_cause = before.death_cause;
+            snake.score = before.score;
+            snake.inputs = before.inputs;
+            snake.power_ups = before.power_ups;
+            snake.grow = before.grow;
+        }
+
+        self.state = tick.state;
+        self.best_score = tick.best_score;
+        self.speed = tick.speed;
+        self.speed_boost = tick.speed_boost;
+        self.tick = tick.tick;
+        self.rng.set_word_pos(tick.word_pos);
+    }
+
+    pub fn rewind_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Steps back up to `ticks` ticks and returns how many it went back.
+    /// A running game comes back paused. Changes made through
+    /// [`Game::set_snake`] or [`Game::set_food`] and ticks from
+    /// [`Game::next_with`] can't be rewound past.
+    pub fn rewind(&mut self, ticks: usize) -> usize {
+        let ticks = ticks.min(self.history.len());
+        if ticks == 0 {
+            return 0;
+        }
+
+        for _ in 0..ticks {
+            let tick = self
+                .history
+                .pop_back()
+                .expect("rewound no further than the history");
+            self.undo(tick);
+        }
+
+        if self.state == GameState::Running {
+            self.state = GameState::Paused;
+        }
+        self.rebuild_grid();
+
+        ticks
+    }
+}
+
+mod test {
+    use crate::*;
+
+    fn running(rewind: usize) -> Game {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(20, 20)
+                .food_amount(3)
+                .rewind(rewind)
+                .build(),
+        )
+        .unwrap()
+        .with_seed(5);
+        game.start().unwrap();
+
+        game
+    }
+
+    #[test]
+    fn rewind_should_restore_earlier_ticks() {
+        let mut game = running(10);
+        game.next().unwrap();
+        let earlier = game.state_hash();
+        let body = game.snakes[0].body.clone();
+        game.next().unwrap();
+        game.next().unwrap();
+
+        assert_eq!(game.rewind(2), 2);
+        assert_eq!(game.tick(), 1);
+        assert_eq!(game.snakes[0].body, body);
+        assert_eq!(game.state(), GameState::Paused);
+
+        game.resume().unwrap();
+        assert_eq!(game.state_hash(), earlier);
+    }
+
+    #[test]
+    fn rewound_game_should_play_the_same() {
+        let mut game = running(10);
+        game.next().unwrap();
+        let mut forward = game.clone();
+        let expected: Vec<_> = (0..3).map(|_| forward.next().unwrap()).collect();
+
+        for _ in 0..3 {
+            game.next().unwrap();
+        }
+        game.rewind(3);
+        game.resume().unwrap();
+
+        let events: Vec<_> = (0..3).map(|_| game.next().unwrap()).collect();
+        assert_eq!(events, expected);
+        assert_eq!(game.state_hash(), forward.state_hash());
+    }
+
+    #[test]
+    fn rewind_should_keep_only_the_last_ticks() {
+        let mut game = running(3);
+        for _ in 0..6 {
+            game.next().unwrap();
+        }
+
+        assert_eq!(game.rewind_len(), 3);
+        assert_eq!(game.rewind(10), 3);
+        assert_eq!(game.tick(), 3);
+        assert_eq!(game.rewind(1), 0);
+
+        let mut game = running(0);
+        game.next().unwrap();
+        assert_eq!(game.rewind(1), 0);
+        assert_eq!(game.tick(), 1);
+    }
+
+    #[test]
+    fn rewind_should_stop_at_a_tick_on_an_outside_rng() {
+        let mut game = running(10);
+        game.next().unwrap();
+        game.next_with(&mut ChaCha8Rng::seed_from_u64(9)).unwrap();
+        assert_eq!(game.rewind_len(), 0);
+
+        game.next().unwrap();
+        let tick = game.tick();
+        game.next().unwrap();
+        assert_eq!(game.rewind(5), 2);
+        assert_eq!(game.tick(), tick - 1);
+    }
+
+    #[test]
+    fn rewind_should_undo_eating_and_shrinking() {
+        let mut game = running(10);
+        game.set_snake(
+            SnakeId(0),
+            Snake::from_body(vec![(1, 5), (2, 5), (3, 5)], Direction::Right).unwrap(),
+        )
+        .unwrap();
+        game.set_food(vec![
+            Food::new(4, 5),
+            Food::with_kind((6, 5), FoodKind::Shrink),
+        ])
+        .unwrap();
+        let before = game.state_hash();
+        let food = game.food().to_vec();
+
+        for _ in 0..3 {
+            game.next().unwrap();
+        }
+        assert!(game.snakes[0].score.points > 0);
+
+        assert_eq!(game.rewind(3), 3);
+        game.resume().unwrap();
+        assert_eq!(game.food(), &food[..]);
+        assert_eq!(game.snakes[0].body, vec![(1, 5), (2, 5), (3, 5)]);
+        assert_eq!(game.snakes[0].score.points, 0);
+        assert_eq!(game.state_hash(), before);
+
+        game.set_food(vec![]).unwrap();
+        assert_eq!(game.rewind_len(), 0);
+    }
+
+    #[test]
+    fn rewind_should_undo_random_play() {
+        for seed in 0..20 {
+            let mut game = Game::from_config(
+                GameConfig::builder()
+                    .size(9, 8)
+                    .food_amount(3)
+                    .wrap(seed % 2 == 0)
+                    .snake((7, 6), Direction::Left)
+                    .rewind(30)
+                    .build(),
+            )
+            .unwrap()
+            .with_seed(seed);
+            game.start().unwrap();
+            let mut bot = Random::new(seed);
+            let mut hashes = vec![];
+
+            for tick in 1..300 {
+                for id in [SnakeId(0), SnakeId(1)] {
+                    if let Some(direction) = bot.next_direction(&game, id) {
+                        game.input(id, direction).unwrap();
+                    }
+                }
+                hashes.push(game.state_hash());
+                if game.next().is_err() || game.state() != GameState::Running {
+                    break;
+                }
+
+                if tick % 37 == 0 {
+                    let ticks = game.rewind(1 + tick as usize % 29);
+                    hashes.truncate(hashes.len() - ticks + 1);
+                    game.resume().unwrap();
+                    assert_eq!(Some(game.state_hash()), hashes.pop());
+                }
+            }
+        }
+    }
+}
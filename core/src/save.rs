@@ -0,0 +1,325 @@
+//! Saved games, either as JSON or as a compact binary file.
+//!
+//! Both forms carry [`SAVE_VERSION`] so old saves are rejected instead of
+//! loading into the wrong shape. The binary form starts with `SNAK` and the
+//! version as a little endian `u32`, followed by the game in postcard.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Game};
+
+pub const SAVE_VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"SNAK";
+
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    game: Game,
+}
+
+impl Game {
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(&SaveRef {
+            version: SAVE_VERSION,
+            game: self,
+        })
+        .map_err(|e| Error::Save(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Game, Error> {
+        let header: Header = serde_json::from_str(json).map_err(|e| Error::Save(e.to_string()))?;
+        check_version(header.version)?;
+
+        let mut save: SaveFile =
+            serde_json::from_str(json).map_err(|e| Error::Save(e.to_string()))?;
+        save.game.config().validate()?;
+        check_state(&save.game)?;
+        save.game.rebuild_grid();
+
+        Ok(save.game)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+
+        postcard::to_extend(self, bytes).map_err(|e| Error::Save(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, Error> {
+        if bytes.len() < 8 || &bytes[..4] != MAGIC {
+            return Err(Error::Save("not a snake save file".into()));
+        }
+
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        check_version(version)?;
+
+        let mut game: Game =
+            postcard::from_bytes(&bytes[8..]).map_err(|e| Error::Save(e.to_string()))?;
+        game.config().validate()?;
+        check_state(&game)?;
+        game.rebuild_grid();
+
+        Ok(game)
+    }
+
+    /// Saves as JSON when the path ends in `.json`, in the binary form otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        if is_json(path) {
+            fs::write(path, self.to_json()?)?;
+        } else {
+            fs::write(path, self.to_bytes()?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Game, Error> {
+        let path = path.as_ref();
+
+        if is_json(path) {
+            Game::from_json(&fs::read_to_string(path)?)
+        } else {
+            Game::from_bytes(&fs::read(path)?)
+        }
+    }
+}
+
+fn check_version(version: u32) -> Result<(), Error> {
+    if version != SAVE_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    Ok(())
+}
+
+// A save is only as good as the file it came from, so everything on the
+// board has to fit the config before the grid is built from it.
+fn check_state(game: &Game) -> Result<(), Error> {
+    let config = game.config();
+    let walls: HashSet<_> = config.walls.iter().copied().collect();
+    let mut taken = HashMap::new();
+
+    for (i, snake) in game.snakes.iter().enumerate() {
+        if snake.id.0 != i {
+            return Err(Error::Save(format!(
+                "{} is stored as snake {}",
+                snake.id, i
+            )));
+        }
+
+        if snake.body.back() != Some(&snake.head_pos) {
+            return Err(Error::Save(format!("{} has no head", snake.id)));
+        }
+
+        if snake
+            .score
+            .last_food_tick()
+            .is_some_and(|last| last > game.tick)
+        {
+            return Err(Error::Save(format!(
+                "{} ate after tick {}",
+                snake.id, game.tick
+            )));
+        }
+
+        for pos in snake.body.iter() {
+            if !config.contains(*pos) {
+                return Err(Error::Save(format!(
+                    "{} is off the board at {:?}",
+                    snake.id, pos
+                )));
+            }
+
+            if !snake.alive {
+                continue;
+            }
+
+            if walls.contains(pos) {
+                return Err(Error::Save(format!(
+                    "{} is on a wall at {:?}",
+                    snake.id, pos
+                )));
+            }
+
+            // A ghost can pass through itself, but never through another snake.
+            if taken.insert(*pos, i).is_some_and(|owner| owner != i) {
+                return Err(Error::Save(format!("snakes overlap at {:?}", pos)));
+            }
+        }
+    }
+
+    let mut food = HashSet::new();
+    for pos in game.food.iter().map(|food| food.pos) {
+        if !config.contains(pos)
+            || walls.contains(&pos)
+            || taken.contains_key(&pos)
+            || !food.insert(pos)
+        {
+            return Err(Error::Save(format!("food {:?} is not on a free cell", pos)));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+mod test {
+    use crate::*;
+
+    fn played() -> Game {
+        let mut game = Game::from_config(
+            GameConfig::builder()
+                .size(12, 12)
+                .food_amount(3)
+                .wall((0, 0))
+                .build(),
+        )
+        .unwrap()
+        .with_seed(7);
+        game.start().unwrap();
+        game.generate_food().unwrap();
+        game.input(SnakeId(0), Direction::Down).unwrap();
+        game.next().unwrap();
+        game.next().unwrap();
+
+        game
+    }
+
+    fn play(mut game: Game) -> Vec<Vec<GameEvent>> {
+        (0..20)
+            .map(|_| {
+                game.input(SnakeId(0), Direction::Left).unwrap();
+                game.input(SnakeId(0), Direction::Down).unwrap();
+                game.next().unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn json_save_should_continue_the_game() {
+        let game = played();
+        let loaded = Game::from_json(&game.to_json().unwrap()).unwrap();
+
+        assert_eq!(loaded.tick(), game.tick());
+        assert_eq!(loaded.seed(), game.seed());
+        assert_eq!(loaded.snakes[0].body, game.snakes[0].body);
+        assert_eq!(play(loaded), play(game));
+    }
+
+    #[test]
+    fn binary_save_should_continue_the_game() {
+        let game = played();
+        let bytes = game.to_bytes().unwrap();
+
+        assert_eq!(&bytes[..4], b"SNAK");
+        assert!(bytes.len() < game.to_json().unwrap().len());
+        assert_eq!(play(Game::from_bytes(&bytes).unwrap()), play(game));
+    }
+
+    fn edit(game: &Game, change: impl FnOnce(&mut serde_json::Value)) -> Result<Game, Error> {
+        let mut json: serde_json::Value = serde_json::from_str(&game.to_json().unwrap()).unwrap();
+        change(&mut json["game"]);
+
+        Game::from_json(&json.to_string())
+    }
+
+    #[test]
+    fn should_reject_saves_that_do_not_fit_the_board() {
+        let game = played();
+        let food = game.food()[0].pos;
+
+        let on_wall = edit(&game, |game| {
+            game["snakes"][0]["body"] = serde_json::json!([[0, 0]]);
+            game["snakes"][0]["head_pos"] = serde_json::json!([0, 0]);
+        });
+        assert!(matches!(on_wall, Err(Error::Save(_))));
+
+        let on_food = edit(&game, |game| {
+            game["snakes"][0]["body"] = serde_json::json!([[food.0, food.1]]);
+            game["snakes"][0]["head_pos"] = serde_json::json!([food.0, food.1]);
+        });
+        assert!(matches!(on_food, Err(Error::Save(_))));
+
+        let off_board = edit(&game, |game| {
+            game["snakes"][0]["body"] = serde_json::json!([[50, 50]]);
+            game["snakes"][0]["head_pos"] = serde_json::json!([50, 50]);
+        });
+        assert!(matches!(off_board, Err(Error::Save(_))));
+
+        let food_off_board = edit(&game, |game| {
+            game["food"][0]["pos"] = serde_json::json!([12, 0]);
+        });
+        assert!(matches!(food_off_board, Err(Error::Save(_))));
+
+        let headless = edit(&game, |game| {
+            game["snakes"][0]["head_pos"] = serde_json::json!([5, 5]);
+        });
+        assert!(matches!(headless, Err(Error::Save(_))));
+
+        let ate_later = edit(&game, |game| {
+            game["snakes"][0]["score"]["last_food_tick"] = serde_json::json!(99);
+        });
+        assert!(matches!(ate_later, Err(Error::Save(_))));
+
+        let mut versus = Game::from_config(
+            GameConfig::builder()
+                .size(12, 12)
+                .start_pos((2, 2))
+                .snake((8, 8), Direction::Left)
+                .build(),
+        )
+        .unwrap();
+        versus.start().unwrap();
+        let overlapping = edit(&versus, |game| {
+            game["snakes"][1]["body"] = serde_json::json!([[2, 2]]);
+            game["snakes"][1]["head_pos"] = serde_json::json!([2, 2]);
+        });
+        assert!(matches!(overlapping, Err(Error::Save(_))));
+    }
+
+    #[test]
+    fn should_reject_other_versions() {
+        let game = played();
+
+        let json = game.to_json().unwrap().replacen(
+            &format!("\"version\": {}", SAVE_VERSION),
+            "\"version\": 99",
+            1,
+        );
+        assert!(matches!(
+            Game::from_json(&json),
+            Err(Error::UnsupportedVersion(99))
+        ));
+
+        let mut bytes = game.to_bytes().unwrap();
+        bytes[4] = 99;
+        assert!(matches!(
+            Game::from_bytes(&bytes),
+            Err(Error::UnsupportedVersion(99))
+        ));
+        assert!(matches!(Game::from_bytes(b"nope"), Err(Error::Save(_))));
+    }
+}
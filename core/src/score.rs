@@ -0,0 +1,55 @@
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreConfig {
+    pub food_points: u32,
+    pub combo_window: u64,
+    pub max_combo: u32,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        ScoreConfig {
+            food_points: 10,
+            combo_window: 20,
+            max_combo: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+    pub points: u32,
+    pub combo: u32,
+    last_food_tick: Option<u64>,
+}
+
+impl Score {
+    pub fn award(&mut self, base: u32, tick: u64, speed: u32, config: &ScoreConfig) -> u32 {
+        self.combo = match self.last_food_tick {
+            Some(last)
+                if tick
+                    .checked_sub(last)
+                    .is_some_and(|gap| gap <= config.combo_window) =>
+            {
+                (self.combo + 1).min(config.max_combo.max(1))
+            }
+            _ => 1,
+        };
+        self.last_food_tick = Some(tick);
+
+        let points = base.saturating_mul(self.combo).saturating_mul(speed.max(1));
+        self.points = self.points.saturating_add(points);
+
+        points
+    }
+
+    pub(crate) fn last_food_tick(&self) -> Option<u64> {
+        self.last_food_tick
+    }
+
+    pub fn break_combo(&mut self) {
+        self.combo = 0;
+        self.last_food_tick = None;
+    }
+}
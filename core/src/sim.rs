@@ -0,0 +1,317 @@
+//! Headless runs of many games spread over all cores.
+//!
+//! Every game gets its own seed, counted up from [`SimConfig::seed`], so a run
+//! gives the same results whatever the number of threads.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Controller, DeathCause, Error, Game, GameConfig, GameState};
+
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    pub game: GameConfig,
+    pub games: usize,
+    pub threads: usize,
+    pub seed: u64,
+    pub max_ticks: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            game: GameConfig::default(),
+            games: 1000,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: 0,
+            max_ticks: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub seed: u64,
+    pub ticks: u64,
+    pub score: u32,
+    pub length: usize,
+    pub state: GameState,
+    pub causes: Vec<DeathCause>,
+}
+
+/// Runs `config.games` games, asking `controller(seed)` for a fresh
+/// controller at the start of each one. It drives every snake.
+pub fn run<F>(config: &SimConfig, controller: F) -> Result<SimReport, Error>
+where
+    F: Fn(u64) -> Box<dyn Controller + Send> + Sync,
+{
+    let mut game_config = config.game.clone();
+    game_config.rewind = 0;
+    game_config.validate()?;
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(config.games));
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= config.games {
+                    break;
+                }
+
+                let seed = config.seed.wrapping_add(i as u64);
+                let result = play(&game_config, seed, config.max_ticks, &mut controller(seed));
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+
+    let elapsed = start.elapsed();
+    let mut games = results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    games.sort_by_key(|result| result.seed);
+
+    Ok(SimReport { games, elapsed })
+}
+
+pub fn play(
+    config: &GameConfig,
+    seed: u64,
+    max_ticks: u64,
+    controller: &mut dyn Controller,
+) -> Result<GameResult, Error> {
+    let mut game = Game::from_config(config.clone())?.with_seed(seed);
+    game.start()?;
+
+    while game.state() == GameState::Running && game.tick() < max_ticks {
+        for id in game
+            .alive_snakes()
+            .map(|snake| snake.id)
+            .collect::<Vec<_>>()
+        {
+            if let Some(direction) = controller.next_direction(&game, id) {
+                game.input(id, direction)?;
+            }
+        }
+
+        game.next()?;
+    }
+
+    Ok(GameResult {
+        seed,
+        ticks: game.tick(),
+        score: game
+            .snakes()
+            .iter()
+            .map(|snake| snake.score.points)
+            .max()
+            .unwrap_or(0),
+        length: game
+            .snakes()
+            .iter()
+            .map(|snake| snake.body().len())
+            .max()
+            .unwrap_or(0),
+        state: game.state(),
+        causes: game
+            .snakes()
+            .iter()
+            .filter_map(|snake| snake.death_cause)
+            .collect(),
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub median: u64,
+    pub p90: u64,
+}
+
+impl Stats {
+    pub fn from_values(mut values: Vec<u64>) -> Stats {
+        if values.is_empty() {
+            return Stats::default();
+        }
+
+        values.sort_unstable();
+        let percentile = |p: usize| values[(values.len() - 1) * p / 100];
+
+        Stats {
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+            median: percentile(50),
+            p90: percentile(90),
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {}  median {}  mean {:.1}  p90 {}  max {}",
+            self.min, self.median, self.mean, self.p90, self.max
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimReport {
+    pub games: Vec<GameResult>,
+    pub elapsed: Duration,
+}
+
+impl SimReport {
+    pub fn total_ticks(&self) -> u64 {
+        self.games.iter().map(|result| result.ticks).sum()
+    }
+
+    pub fn ticks_per_second(&self) -> f64 {
+        self.total_ticks() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn ticks(&self) -> Stats {
+        Stats::from_values(self.games.iter().map(|result| result.ticks).collect())
+    }
+
+    pub fn scores(&self) -> Stats {
+        Stats::from_values(
+            self.games
+                .iter()
+                .map(|result| result.score as u64)
+                .collect(),
+        )
+    }
+
+    pub fn lengths(&self) -> Stats {
+        Stats::from_values(
+            self.games
+                .iter()
+                .map(|result| result.length as u64)
+                .collect(),
+        )
+    }
+
+    pub fn outcomes(&self) -> BTreeMap<&'static str, usize> {
+        let mut outcomes = BTreeMap::new();
+
+        for result in self.games.iter() {
+            let outcome = match result.state {
+                GameState::Won => "won",
+                GameState::GameOver => "game over",
+                _ => "out of ticks",
+            };
+            *outcomes.entry(outcome).or_default() += 1;
+        }
+
+        outcomes
+    }
+
+    pub fn death_causes(&self) -> BTreeMap<&'static str, usize> {
+        let mut causes = BTreeMap::new();
+
+        for cause in self.games.iter().flat_map(|result| result.causes.iter()) {
+            let name = match cause {
+                DeathCause::Edge => "edge",
+                DeathCause::SelfCollision => "self",
+                DeathCause::Wall => "wall",
+                DeathCause::HeadOn(_) => "head-on",
+                DeathCause::Body(_) => "other snake",
+            };
+            *causes.entry(name).or_default() += 1;
+        }
+
+        causes
+    }
+}
+
+impl fmt::Display for SimReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games, {} ticks in {:.2?} ({:.0} ticks/s)",
+            self.games.len(),
+            self.total_ticks(),
+            self.elapsed,
+            self.ticks_per_second()
+        )?;
+        writeln!(f, "ticks:  {}", self.ticks())?;
+        writeln!(f, "score:  {}", self.scores())?;
+        writeln!(f, "length: {}", self.lengths())?;
+
+        write!(f, "outcome:")?;
+        for (outcome, count) in self.outcomes() {
+            write!(f, "  {} {}", outcome, count)?;
+        }
+        writeln!(f)?;
+
+        write!(f, "deaths:")?;
+        for (cause, count) in self.death_causes() {
+            write!(f, "  {} {}", cause, count)?;
+        }
+        writeln!(f)
+    }
+}
+
+mod test {
+    use crate::*;
+
+    fn config(threads: usize) -> sim::SimConfig {
+        sim::SimConfig {
+            game: GameConfig::builder().size(12, 12).build(),
+            games: 40,
+            threads,
+            seed: 3,
+            max_ticks: 500,
+        }
+    }
+
+    #[test]
+    fn sim_should_not_depend_on_threads() {
+        let one = sim::run(&config(1), |seed| Box::new(Random::new(seed))).unwrap();
+        let four = sim::run(&config(4), |seed| Box::new(Random::new(seed))).unwrap();
+
+        assert_eq!(one.games, four.games);
+        assert_eq!(one.games.len(), 40);
+        assert_eq!(one.games[0].seed, 3);
+    }
+
+    #[test]
+    fn sim_should_report_deaths() {
+        let report = sim::run(&config(2), |_| Box::new(Scripted::default())).unwrap();
+
+        assert_eq!(report.death_causes().get("edge"), Some(&40));
+        assert_eq!(report.outcomes().get("game over"), Some(&40));
+        assert_eq!(report.ticks().max, 7);
+        assert!(report.to_string().contains("40 games"));
+    }
+
+    #[test]
+    fn sim_should_stop_at_max_ticks() {
+        let mut config = config(2);
+        config.game.wrap = true;
+        config.game.food_amount = 0;
+        config.max_ticks = 50;
+
+        let report = sim::run(&config, |_| Box::new(Scripted::default())).unwrap();
+        assert_eq!(report.ticks().min, 50);
+        assert_eq!(report.outcomes().get("out of ticks"), Some(&40));
+    }
+}
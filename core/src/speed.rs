@@ -0,0 +1,112 @@
+use std::{str::FromStr, time::Duration};
+
+use crate::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedBasis {
+    Length,
+    Score,
+    Level(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpeedCurve {
+    pub basis: SpeedBasis,
+    pub base_ms: u64,
+    pub step_ms: u64,
+    pub min_ms: u64,
+    pub every: u32,
+    pub max_level: u32,
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        SpeedCurve::normal()
+    }
+}
+
+impl SpeedCurve {
+    pub fn easy() -> SpeedCurve {
+        SpeedCurve {
+            basis: SpeedBasis::Length,
+            base_ms: 250,
+            step_ms: 10,
+            min_ms: 120,
+            every: 5,
+            max_level: 10,
+        }
+    }
+
+    pub fn normal() -> SpeedCurve {
+        SpeedCurve {
+            basis: SpeedBasis::Length,
+            base_ms: 200,
+            step_ms: 10,
+            min_ms: 80,
+            every: 4,
+            max_level: 15,
+        }
+    }
+
+    pub fn insane() -> SpeedCurve {
+        SpeedCurve {
+            basis: SpeedBasis::Length,
+            base_ms: 120,
+            step_ms: 8,
+            min_ms: 40,
+            every: 2,
+            max_level: 20,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.every == 0 || self.max_level == 0 {
+            return Err(Error::InvalidConfig(
+                "speed curve needs a step of at least one and a max level".into(),
+            ));
+        }
+
+        if self.min_ms == 0 || self.base_ms < self.min_ms {
+            return Err(Error::InvalidConfig(format!(
+                "speed curve interval {}ms must be above its minimum {}ms",
+                self.base_ms, self.min_ms
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn level(&self, length: u32, score: u32) -> u32 {
+        let level = match self.basis {
+            SpeedBasis::Length => 1 + length / self.every,
+            SpeedBasis::Score => 1 + score / self.every,
+            SpeedBasis::Level(level) => level,
+        };
+
+        level.clamp(1, self.max_level)
+    }
+
+    pub fn interval(&self, level: u32) -> Duration {
+        let faster = self.step_ms.saturating_mul(level.saturating_sub(1) as u64);
+
+        Duration::from_millis(self.base_ms.saturating_sub(faster).max(self.min_ms))
+    }
+}
+
+impl FromStr for SpeedCurve {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(SpeedCurve::easy()),
+            "normal" => Ok(SpeedCurve::normal()),
+            "insane" => Ok(SpeedCurve::insane()),
+            s => Err(Error::InvalidConfig(format!(
+                "unknown speed preset '{}'",
+                s
+            ))),
+        }
+    }
+}
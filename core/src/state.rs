@@ -0,0 +1,23 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    Ready,
+    Running,
+    Paused,
+    GameOver,
+    Won,
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameState::Ready => write!(f, "ready"),
+            GameState::Running => write!(f, "running"),
+            GameState::Paused => write!(f, "paused"),
+            GameState::GameOver => write!(f, "game over"),
+            GameState::Won => write!(f, "won"),
+        }
+    }
+}
@@ -1,10 +1,9 @@
 #![allow(warnings)]
 
-use core::{self, Direction};
+use core::{self, cli::Args, Controller, Direction, FoodKind};
 use std::{
     fs::{create_dir_all, exists, remove_dir_all},
     io,
-    time::Duration,
 };
 
 use image::ImageBuffer;
@@ -18,75 +17,171 @@ use tokio::{fs::rename, sync::mpsc, time::sleep};
 const BACKGROUND_COLOR: [[u8; 3]; 2] = [[0u8, 255u8, 0u8], [136, 255, 136]];
 const SNAKE_COLOR: [u8; 3] = [0u8, 0u8, 255u8];
 const FOOD_COLOR: [u8; 3] = [255u8, 0u8, 0u8];
-const WIDTH: usize = 9;
-const HEIGHT: usize = 6;
+const GOLDEN_FOOD_COLOR: [u8; 3] = [255u8, 200u8, 0u8];
+const POISON_FOOD_COLOR: [u8; 3] = [128u8, 0u8, 128u8];
+const SPEED_UP_FOOD_COLOR: [u8; 3] = [255u8, 255u8, 255u8];
+const SLOW_DOWN_FOOD_COLOR: [u8; 3] = [0u8, 128u8, 128u8];
+const SHRINK_FOOD_COLOR: [u8; 3] = [0u8, 0u8, 0u8];
+const GHOST_FOOD_COLOR: [u8; 3] = [200u8, 200u8, 200u8];
+const SHIELD_FOOD_COLOR: [u8; 3] = [0u8, 0u8, 128u8];
+const MAGNET_FOOD_COLOR: [u8; 3] = [255u8, 128u8, 0u8];
+const WALL_COLOR: [u8; 3] = [64u8, 64u8, 64u8];
 const IMAGE_SIZE: u32 = 100;
 const PATH: &str = "/home/sandev/game_snake";
+const LEVEL: &str = include_str!("../../levels/small.txt");
 
 struct DrawGame {
     pub game: core::Game,
-    old_pos: (u16, u16),
 }
 
 impl DrawGame {
-    pub fn new() -> DrawGame {
+    pub fn new(game: core::Game) -> DrawGame {
         if let Ok(true) = exists(PATH) {
             let _ = remove_dir_all(PATH);
         }
 
         create_dir_all(PATH).unwrap();
 
-        DrawGame {
-            game: core::Game::new((3, 3), WIDTH as u16, HEIGHT as u16),
-            old_pos: (3, 3),
-        }
+        DrawGame { game }
+    }
+
+    pub fn draw_board(&self) {
+        self.draw_background();
+        self.draw_walls();
+        self.draw_snakes();
+        self.draw_food();
     }
 
     pub fn draw_background(&self) {
-        for i in 0..WIDTH {
-            for j in 0..HEIGHT {
-                DrawGame::create_image(BACKGROUND_COLOR[(i + j) % 2], (i as u16, j as u16));
+        for i in 0..self.game.width() {
+            for j in 0..self.game.height() {
+                self.create_image(BACKGROUND_COLOR[((i + j) % 2) as usize], (i, j));
+            }
+        }
+    }
+
+    pub fn draw_snakes(&self) {
+        for snake in self.game.snakes().iter() {
+            for pos in snake.body() {
+                self.create_image(SNAKE_COLOR, *pos);
             }
         }
     }
 
-    pub fn draw_snake(&self) {
-        for pos in &self.game.snake.body {
-            DrawGame::create_image(SNAKE_COLOR, *pos);
+    pub fn draw_walls(&self) {
+        for pos in self.game.walls() {
+            self.create_image(WALL_COLOR, *pos);
         }
     }
 
     pub fn draw_food(&self) {
-        for core::Food { pos } in self.game.food.iter() {
-            DrawGame::create_image(FOOD_COLOR, *pos);
+        for core::Food { pos, kind } in self.game.food().iter() {
+            self.create_image(food_color(*kind), *pos);
         }
     }
 
-    fn create_image(color: [u8; 3], pos: (u16, u16)) {
+    fn clear(&self, pos: (u16, u16)) {
+        self.create_image(BACKGROUND_COLOR[(pos.0 + pos.1) as usize % 2], pos);
+    }
+
+    fn create_image(&self, color: [u8; 3], pos: (u16, u16)) {
         ImageBuffer::from_pixel(IMAGE_SIZE, IMAGE_SIZE, image::Rgb(color))
-            .save(format!("{}/{}.png", PATH, pos.0 + (pos.1 * WIDTH as u16)))
+            .save(format!(
+                "{}/{}.png",
+                PATH,
+                pos.0 as u32 + (pos.1 as u32 * self.game.width() as u32)
+            ))
             .unwrap();
     }
 
-    fn before_next(&mut self) {
-        self.old_pos = self.game.snake.body[0];
+    // Redraws a cell from whatever is on it now, so it doesn't matter in
+    // which order the events of a tick touched it.
+    fn redraw(&self, pos: (u16, u16)) {
+        if let Some(food) = self.game.food_at(pos) {
+            self.create_image(food_color(food.kind), pos);
+        } else if self.game.is_wall(pos) {
+            self.create_image(WALL_COLOR, pos);
+        } else if self.game.is_occupied(pos) {
+            self.create_image(SNAKE_COLOR, pos);
+        } else {
+            self.clear(pos);
+        }
+    }
+
+    fn draw_events(&self, events: &[core::GameEvent]) {
+        let mut touched = vec![];
+
+        for event in events {
+            match event {
+                core::GameEvent::Moved { head, vacated, .. } => {
+                    touched.extend(*vacated);
+                    touched.push(*head);
+                }
+                core::GameEvent::AteFood { id, .. } => println!(
+                    "{} score: {} (best {})",
+                    id,
+                    self.game.snakes()[id.0].score.points,
+                    self.game.best_score()
+                ),
+                core::GameEvent::Shrank { vacated, .. } => touched.push(*vacated),
+                core::GameEvent::FoodSpawned { pos, .. } => touched.push(*pos),
+                core::GameEvent::FoodMoved { from, to } => touched.extend([*from, *to]),
+                core::GameEvent::Absorbed { id, cause } => {
+                    println!("{} shield absorbed {}", id, cause)
+                }
+                core::GameEvent::PowerUpEnded { id, kind } => println!("{} {} ended", id, kind),
+                core::GameEvent::Died { id, cause } => println!("{} {}", id, cause),
+                core::GameEvent::Won => println!("you won!"),
+                _ => (),
+            }
+        }
+
+        touched.sort_unstable();
+        touched.dedup();
+        for pos in touched {
+            self.redraw(pos);
+        }
     }
+}
 
-    fn after_next(&mut self) {
-        let new_pos = self.game.snake.body.last().unwrap();
+fn food_color(kind: FoodKind) -> [u8; 3] {
+    match kind {
+        FoodKind::Normal => FOOD_COLOR,
+        FoodKind::Golden => GOLDEN_FOOD_COLOR,
+        FoodKind::Poison => POISON_FOOD_COLOR,
+        FoodKind::SpeedUp => SPEED_UP_FOOD_COLOR,
+        FoodKind::SlowDown => SLOW_DOWN_FOOD_COLOR,
+        FoodKind::Shrink => SHRINK_FOOD_COLOR,
+        FoodKind::Ghost => GHOST_FOOD_COLOR,
+        FoodKind::Shield => SHIELD_FOOD_COLOR,
+        FoodKind::Magnet => MAGNET_FOOD_COLOR,
+    }
+}
 
-        DrawGame::create_image(SNAKE_COLOR, *new_pos);
-        DrawGame::create_image(
-            BACKGROUND_COLOR[(self.old_pos.0 + self.old_pos.1) as usize % 2],
-            self.old_pos,
-        );
+fn load_game(args: &Args) -> Result<core::Game, core::Error> {
+    let mut game = args.level(LEVEL)?.into_game()?;
+    if let Some(seed) = args.value("--seed")? {
+        game = game.with_seed(seed);
     }
+
+    Ok(game)
 }
 
 #[tokio::main]
 async fn main() {
     let (tx, mut rx) = mpsc::unbounded_channel();
-    let mut draw = DrawGame::new();
+    let args = Args::from_env();
+    let game = match load_game(&args) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut draw = DrawGame::new(game);
+    let mut keyboard = core::Keyboard::new();
+    println!("seed: {}", draw.game.seed());
 
     tokio::spawn(async move {
         if let Err(e) = listen(move |event| match event.event_type {
@@ -99,27 +194,49 @@ async fn main() {
         }
     });
 
-    draw.draw_background();
-    draw.draw_snake();
+    draw.draw_board();
+    println!("press space to start");
 
     loop {
         while let Ok(event) = rx.try_recv() {
             match event {
-                DownArrow => draw.game.input(Direction::Down),
-                UpArrow => draw.game.input(Direction::Up),
-                LeftArrow => draw.game.input(Direction::Left),
-                RightArrow => draw.game.input(Direction::Right),
-                Key::KeyR => draw.game.reset(),
+                DownArrow => keyboard.press(Direction::Down),
+                UpArrow => keyboard.press(Direction::Up),
+                LeftArrow => keyboard.press(Direction::Left),
+                RightArrow => keyboard.press(Direction::Right),
+                Key::Space => {
+                    let _ = match draw.game.state() {
+                        core::GameState::Ready => draw.game.start(),
+                        core::GameState::Running => draw.game.pause(),
+                        core::GameState::Paused => draw.game.resume(),
+                        _ => Ok(()),
+                    };
+                    println!("{}", draw.game.state());
+                }
+                Key::KeyR => {
+                    draw.game.reset();
+                    keyboard.clear();
+                    draw.draw_board();
+                    println!("press space to start");
+                }
                 Key::KeyQ => std::process::exit(0),
                 _ => (),
             }
         }
-        draw.before_next();
-        draw.game.next();
-
-        draw.after_next();
-        draw.draw_food();
+        if draw.game.state() == core::GameState::Running {
+            if let Some(direction) = keyboard.next_direction(&draw.game, core::SnakeId(0)) {
+                let _ = draw.game.input(core::SnakeId(0), direction);
+            }
+        }
+        match draw.game.next() {
+            Ok(events) => draw.draw_events(&events),
+            Err(core::Error::DeadSnake) => (),
+            Err(e) => {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
 
-        sleep(Duration::from_millis(5000)).await;
+        sleep(draw.game.tick_interval()).await;
     }
 }
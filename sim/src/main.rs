@@ -0,0 +1,66 @@
+use core::{
+    cli::Args,
+    sim::{self, SimConfig},
+    Autopilot, Hamiltonian, Random, Scripted,
+};
+
+const LEVEL: &str = include_str!("../../levels/classic.txt");
+
+fn load_config(args: &Args) -> Result<SimConfig, core::Error> {
+    let mut config = SimConfig {
+        game: args.level(LEVEL)?.config,
+        ..SimConfig::default()
+    };
+    if let Some(games) = args.value("--games")? {
+        config.games = games;
+    }
+    if let Some(threads) = args.value("--threads")? {
+        config.threads = threads;
+    }
+    if let Some(seed) = args.value("--seed")? {
+        config.seed = seed;
+    }
+    if let Some(max_ticks) = args.value("--max-ticks")? {
+        config.max_ticks = max_ticks;
+    }
+
+    Ok(config)
+}
+
+fn main() {
+    let args = Args::from_env();
+    let config = match load_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let controller = match args.value::<String>("--controller") {
+        Ok(controller) => controller.unwrap_or_else(|| "random".to_string()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let report = match controller.as_str() {
+        "random" => sim::run(&config, |seed| Box::new(Random::new(seed))),
+        "autopilot" => sim::run(&config, |_| Box::new(Autopilot::new())),
+        "hamiltonian" => Hamiltonian::new(&config.game)
+            .and_then(|solver| sim::run(&config, |_| Box::new(solver.clone()))),
+        "straight" => sim::run(&config, |_| Box::new(Scripted::default())),
+        _ => {
+            eprintln!("Error: unknown controller {:?}", controller);
+            std::process::exit(1);
+        }
+    };
+
+    match report {
+        Ok(report) => print!("{}", report),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(warnings)]
 
-use core::{self, Food};
+use core::{self, cli::Args, Controller, Food, FoodKind};
 use crossterm::{
     self,
     cursor::{DisableBlinking, Hide, MoveTo, SetCursorStyle, Show},
@@ -20,16 +20,25 @@ use tokio::{
     time::sleep,
 };
 
-const SIZE_GAME: u16 = 20;
 const PERFECT_SQUARE: [u16; 2] = [5, 2];
+const LEVEL: &str = include_str!("../../levels/classic.txt");
+const SAVE_PATH: &str = "snake.save";
+const SNAKE_COLORS: [Color; 4] = [Color::Blue, Color::Magenta, Color::Yellow, Color::Cyan];
 
 enum Events {
-    Input(core::Direction),
+    Input(core::SnakeId, core::Direction),
+    Turn(core::SnakeId, core::Turn),
+    Pause,
     Reset,
+    Save,
+    Load,
+    Rewind,
+    Autopilot,
 }
 
 struct DrawGame {
     game: core::Game,
+    message: String,
 }
 
 impl DrawGame {
@@ -43,8 +52,8 @@ impl DrawGame {
             },
         ];
 
-        for i in 0..SIZE_GAME {
-            for j in 0..SIZE_GAME {
+        for i in 0..self.game.width() {
+            for j in 0..self.game.height() {
                 for k in 0..PERFECT_SQUARE[1] {
                     execute!(
                         stdout(),
@@ -62,8 +71,30 @@ impl DrawGame {
         }
     }
 
-    fn draw_snake(&self) {
-        for pos in self.game.snake.body.iter() {
+    fn draw_snakes(&self) {
+        for snake in self.game.alive_snakes() {
+            let color = SNAKE_COLORS[snake.id.0 % SNAKE_COLORS.len()];
+
+            for pos in snake.body().iter() {
+                for i in 0..PERFECT_SQUARE[1] {
+                    execute!(
+                        stdout(),
+                        MoveTo(
+                            pos.0 * PERFECT_SQUARE[0] + PERFECT_SQUARE[0],
+                            pos.1 * PERFECT_SQUARE[1] + i
+                        ),
+                        SetBackgroundColor(color),
+                        Print(str::repeat(" ", PERFECT_SQUARE[0] as usize)),
+                        ResetColor
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    fn draw_food(&self) {
+        for Food { pos, kind } in self.game.food().iter() {
             for i in 0..PERFECT_SQUARE[1] {
                 execute!(
                     stdout(),
@@ -71,7 +102,7 @@ impl DrawGame {
                         pos.0 * PERFECT_SQUARE[0] + PERFECT_SQUARE[0],
                         pos.1 * PERFECT_SQUARE[1] + i
                     ),
-                    SetBackgroundColor(Color::Blue),
+                    SetBackgroundColor(food_color(*kind)),
                     Print(str::repeat(" ", PERFECT_SQUARE[0] as usize)),
                     ResetColor
                 )
@@ -80,8 +111,8 @@ impl DrawGame {
         }
     }
 
-    fn draw_food(&self) {
-        for Food { pos } in self.game.food.iter() {
+    fn draw_walls(&self) {
+        for pos in self.game.walls().iter() {
             for i in 0..PERFECT_SQUARE[1] {
                 execute!(
                     stdout(),
@@ -89,7 +120,7 @@ impl DrawGame {
                         pos.0 * PERFECT_SQUARE[0] + PERFECT_SQUARE[0],
                         pos.1 * PERFECT_SQUARE[1] + i
                     ),
-                    SetBackgroundColor(Color::DarkRed),
+                    SetBackgroundColor(Color::DarkGrey),
                     Print(str::repeat(" ", PERFECT_SQUARE[0] as usize)),
                     ResetColor
                 )
@@ -98,15 +129,175 @@ impl DrawGame {
         }
     }
 
+    fn draw_info(&self) {
+        execute!(
+            stdout(),
+            MoveTo(PERFECT_SQUARE[0], self.game.height() * PERFECT_SQUARE[1]),
+            Clear(ClearType::CurrentLine),
+            Print(format!(
+                "{}  best: {}  speed: {}  seed: {}",
+                self.game
+                    .snakes()
+                    .iter()
+                    .map(|snake| format!(
+                        "{}: {} x{}{}",
+                        snake.id,
+                        snake.score.points,
+                        snake.score.combo,
+                        snake
+                            .power_ups()
+                            .iter()
+                            .map(|power_up| format!(" {} {}", power_up.kind, power_up.ticks))
+                            .collect::<String>()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("  "),
+                self.game.best_score(),
+                self.game.speed(),
+                self.game.seed()
+            )),
+        )
+        .unwrap();
+    }
+
+    fn draw_status(&self, status: &str) {
+        execute!(
+            stdout(),
+            MoveTo(
+                PERFECT_SQUARE[0],
+                self.game.height() * PERFECT_SQUARE[1] + 1
+            ),
+            Clear(ClearType::CurrentLine),
+            Print(status),
+        )
+        .unwrap();
+    }
+
+    fn draw_state(&self) {
+        let status = match self.game.state() {
+            core::GameState::Ready => "press space to start".to_string(),
+            core::GameState::Running => String::new(),
+            core::GameState::Paused => "paused, press space to resume".to_string(),
+            core::GameState::GameOver => {
+                let causes: Vec<String> = self
+                    .game
+                    .snakes()
+                    .iter()
+                    .filter_map(|snake| match snake.death_cause {
+                        Some(cause) if self.game.snakes().len() > 1 => {
+                            Some(format!("{} {}", snake.id, cause))
+                        }
+                        Some(cause) => Some(cause.to_string()),
+                        None => None,
+                    })
+                    .collect();
+
+                format!("game over: {}, press r to restart", causes.join(", "))
+            }
+            core::GameState::Won => "you won! press r to play again".to_string(),
+        };
+
+        if self.message.is_empty() {
+            self.draw_status(&status);
+        } else {
+            self.draw_status(&format!("{}  {}", status, self.message));
+        }
+    }
+
     fn render(&mut self) {}
 }
 
+fn food_color(kind: FoodKind) -> Color {
+    match kind {
+        FoodKind::Normal => Color::DarkRed,
+        FoodKind::Golden => Color::DarkYellow,
+        FoodKind::Poison => Color::DarkMagenta,
+        FoodKind::SpeedUp => Color::White,
+        FoodKind::SlowDown => Color::DarkCyan,
+        FoodKind::Shrink => Color::Black,
+        FoodKind::Ghost => Color::Rgb {
+            r: 190,
+            g: 170,
+            b: 255,
+        },
+        FoodKind::Shield => Color::DarkBlue,
+        FoodKind::Magnet => Color::Red,
+    }
+}
+
+fn load_game(args: &Args) -> Result<core::Game, core::Error> {
+    let mut game = args.level(LEVEL)?.into_game()?;
+    if let Some(seed) = args.value("--seed")? {
+        game = game.with_seed(seed);
+    }
+
+    Ok(game)
+}
+
 #[tokio::main]
 async fn main() {
+    let args = Args::from_env();
+    let replay = match args
+        .value::<String>("--replay")
+        .and_then(|path| path.map(core::Replay::load).transpose())
+    {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let game = match replay
+        .as_ref()
+        .map_or_else(|| load_game(&args), |replay| replay.game())
+    {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let (record_path, save_path) = match (
+        args.value::<String>("--record"),
+        args.value::<String>("--save"),
+    ) {
+        (Ok(record_path), Ok(save_path)) => (
+            record_path.map(|path| {
+                let path = std::path::PathBuf::from(path);
+                match path.extension() {
+                    Some(_) => path,
+                    None => path.with_extension(core::REPLAY_EXTENSION),
+                }
+            }),
+            save_path.unwrap_or_else(|| SAVE_PATH.to_string()),
+        ),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut recorder = record_path.as_ref().map(|_| core::Recorder::new(&game));
+    let mut player = replay.as_ref().map(|replay| replay.player());
+
     enable_raw_mode().unwrap();
     execute!(stdout(), Clear(ClearType::All), Hide).unwrap();
+
+    let first = core::SnakeId(0);
+    let second = core::SnakeId(if game.snakes().len() > 1 { 1 } else { 0 });
+    let relative = args.flag("--relative");
+    let keyboards: Vec<core::Keyboard> = game
+        .snakes()
+        .iter()
+        .map(|_| core::Keyboard::new())
+        .collect();
+    let mut controllers: Vec<Box<dyn core::Controller>> = keyboards
+        .iter()
+        .map(|keyboard| Box::new(keyboard.clone()) as Box<dyn core::Controller>)
+        .collect();
+    let mut autopilot = false;
     let mut draw = DrawGame {
-        game: core::Game::new((5, 5), 20, 20),
+        game,
+        message: String::new(),
     };
     let (tx, mut rx) = mpsc::unbounded_channel();
 
@@ -123,17 +314,29 @@ async fn main() {
                         execute!(stdout(), Show, ResetColor).unwrap();
                         std::process::exit(0);
                     }
-                    KeyCode::Up | KeyCode::Char('w') => Some(Events::Input(core::Direction::Up)),
-                    KeyCode::Down | KeyCode::Char('s') => {
-                        Some(Events::Input(core::Direction::Down))
-                    }
-                    KeyCode::Left | KeyCode::Char('a') => {
-                        Some(Events::Input(core::Direction::Left))
-                    }
-                    KeyCode::Right | KeyCode::Char('d') => {
-                        Some(Events::Input(core::Direction::Right))
+                    KeyCode::Left if relative => Some(Events::Turn(first, core::Turn::Left)),
+                    KeyCode::Right if relative => Some(Events::Turn(first, core::Turn::Right)),
+                    KeyCode::Char('a') if relative => Some(Events::Turn(second, core::Turn::Left)),
+                    KeyCode::Char('d') if relative => Some(Events::Turn(second, core::Turn::Right)),
+                    KeyCode::Up | KeyCode::Down | KeyCode::Char('w') | KeyCode::Char('s')
+                        if relative =>
+                    {
+                        None
                     }
+                    KeyCode::Up => Some(Events::Input(first, core::Direction::Up)),
+                    KeyCode::Down => Some(Events::Input(first, core::Direction::Down)),
+                    KeyCode::Left => Some(Events::Input(first, core::Direction::Left)),
+                    KeyCode::Right => Some(Events::Input(first, core::Direction::Right)),
+                    KeyCode::Char('w') => Some(Events::Input(second, core::Direction::Up)),
+                    KeyCode::Char('s') => Some(Events::Input(second, core::Direction::Down)),
+                    KeyCode::Char('a') => Some(Events::Input(second, core::Direction::Left)),
+                    KeyCode::Char('d') => Some(Events::Input(second, core::Direction::Right)),
+                    KeyCode::Char(' ') | KeyCode::Char('p') => Some(Events::Pause),
                     KeyCode::Char('r') => Some(Events::Reset),
+                    KeyCode::F(5) => Some(Events::Save),
+                    KeyCode::F(9) => Some(Events::Load),
+                    KeyCode::Backspace => Some(Events::Rewind),
+                    KeyCode::Char('b') => Some(Events::Autopilot),
                     e => None,
                 },
                 _ => None,
@@ -148,16 +351,131 @@ async fn main() {
     loop {
         while let Ok(event) = rx.try_recv() {
             match event {
-                Events::Input(input) => draw.game.input(input),
-                Events::Reset => draw.game.reset()
+                Events::Input(id, input) if player.is_none() => {
+                    if let Some(keyboard) = keyboards.get(id.0) {
+                        keyboard.press(input);
+                    }
+                }
+                Events::Input(..) => (),
+                Events::Turn(id, turn) if player.is_none() => {
+                    if let Some(keyboard) = keyboards.get(id.0) {
+                        keyboard.press_turn(turn);
+                    }
+                }
+                Events::Turn(..) => (),
+                Events::Pause => {
+                    let _ = match draw.game.state() {
+                        core::GameState::Ready => draw.game.start(),
+                        core::GameState::Running => draw.game.pause(),
+                        core::GameState::Paused => draw.game.resume(),
+                        _ => Ok(()),
+                    };
+                }
+                Events::Rewind if player.is_none() => {
+                    if draw.game.rewind(1) > 0 {
+                        draw.message = format!("rewound to tick {}", draw.game.tick());
+                        if recorder.take().is_some() {
+                            draw.message.push_str(", recording stopped");
+                        }
+                    }
+                }
+                Events::Rewind => (),
+                Events::Autopilot if player.is_none() => {
+                    autopilot = !autopilot;
+                    keyboards[first.0].clear();
+                    controllers[first.0] = match autopilot {
+                        true => Box::new(core::Autopilot::new()),
+                        false => Box::new(keyboards[first.0].clone()),
+                    };
+                    draw.message = match autopilot {
+                        true => "autopilot on, press b to take over".to_string(),
+                        false => "autopilot off".to_string(),
+                    };
+                }
+                Events::Autopilot => (),
+                Events::Reset => {
+                    draw.game.reset();
+                    keyboards.iter().for_each(core::Keyboard::clear);
+                    if let Some(replay) = replay.as_ref() {
+                        draw.game = replay.game().unwrap();
+                        player = Some(replay.player());
+                    }
+                    if record_path.is_some() {
+                        recorder = Some(core::Recorder::new(&draw.game));
+                    }
+                }
+                Events::Save => {
+                    draw.message = match draw.game.save(&save_path) {
+                        Ok(()) => format!("saved to {}", save_path),
+                        Err(e) => format!("save failed: {}", e),
+                    };
+                }
+                Events::Load => {
+                    draw.message = match core::Game::load(&save_path) {
+                        Ok(game) => {
+                            draw.game = game;
+                            player = None;
+                            recorder = None;
+                            execute!(stdout(), Clear(ClearType::All)).unwrap();
+                            format!("loaded {}", save_path)
+                        }
+                        Err(e) => format!("load failed: {}", e),
+                    };
+                }
             }
         }
 
         draw.draw_background();
-        draw.draw_snake();
-        draw.game.next();
+        draw.draw_walls();
+        draw.draw_snakes();
+        let state = draw.game.state();
+        if state == core::GameState::Running && player.is_none() {
+            for (i, controller) in controllers.iter_mut().enumerate() {
+                let id = core::SnakeId(i);
+                if let Some(input) = controller.next_direction(&draw.game, id) {
+                    let _ = match recorder.as_mut() {
+                        Some(recorder) => recorder.input(&mut draw.game, id, input),
+                        None => draw.game.input(id, input),
+                    };
+                }
+            }
+        }
+        let result = match (player.as_mut(), recorder.as_mut()) {
+            (Some(player), _) if state == core::GameState::Running => {
+                player.step(&mut draw.game).unwrap_or_else(|| {
+                    draw.message = "replay finished".to_string();
+                    Ok(vec![])
+                })
+            }
+            (Some(_), _) => Ok(vec![]),
+            (None, Some(recorder)) => recorder.next(&mut draw.game),
+            (None, None) => draw.game.next(),
+        };
+        match result {
+            Ok(_) | Err(core::Error::DeadSnake) => (),
+            Err(e) => {
+                disable_raw_mode();
+                execute!(stdout(), Show, ResetColor).unwrap();
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        if let (Some(recorder), Some(path)) = (recorder.as_ref(), record_path.as_ref()) {
+            let finished = matches!(
+                draw.game.state(),
+                core::GameState::GameOver | core::GameState::Won
+            );
+            if finished && state == core::GameState::Running {
+                draw.message = match recorder.replay().save(path) {
+                    Ok(()) => format!("replay saved to {}", path.display()),
+                    Err(e) => format!("replay not saved: {}", e),
+                };
+            }
+        }
         draw.draw_food();
-        sleep(Duration::from_millis(200)).await;
+        draw.draw_info();
+        draw.draw_state();
+        sleep(draw.game.tick_interval()).await;
     }
 
     futures::future::pending::<()>().await;